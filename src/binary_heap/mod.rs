@@ -10,6 +10,7 @@
 //!
 //! <https://github.com/rust-lang/rust/blob/60bd3f96779dbe6bd206dae09395e9af7d580552/library/alloc/src/collections/binary_heap/tests.rs>
 
+use std::cmp::Ordering;
 use std::vec;
 
 /// A priority queue implemented with a binary heap.
@@ -44,7 +45,7 @@ impl<T: Ord> BinaryHeap<T> {
 
     /// Returns the least item in the binary heap, or [`None`] if it is empty.
     pub fn peek(&self) -> Option<&T> {
-        self.data.get(0)
+        self.data.first()
     }
 
     /// Removes the least item from the binary heap and returns it, or [`None`] if it
@@ -96,41 +97,61 @@ impl<T: Ord> BinaryHeap<T> {
     /// Take an element at `pos` and move it down the heap,
     /// while its children are smaller.
     fn sift_down(&mut self, pos: usize) {
-        let mut index = pos;
-        let items = &mut self.data;
-        let size = items.len();
-        let half_size = size / 2;
-
-        while index < half_size {
-            let left_index = index * 2 + 1;
-            let right_index = left_index + 1;
-
-            // If the left or right node is smaller, swap with the smaller of those.
-            if items[left_index] < items[index] {
-                if right_index < size && items[right_index] < items[left_index] {
-                    items.swap(index, right_index);
-                    index = right_index;
-                } else {
-                    items.swap(index, left_index);
-                    index = left_index;
-                }
-            } else if right_index < size && items[right_index] < items[index] {
+        sift_down_by(&mut self.data, pos, &mut T::cmp);
+    }
+
+    fn rebuild(&mut self) {
+        heapify_by(&mut self.data, &mut T::cmp);
+    }
+}
+
+/// Take an element at `pos` and move it down the heap `items[]`,
+/// while its children are smaller according to `compare`.
+pub(crate) fn sift_down_by<T, F>(items: &mut [T], pos: usize, compare: &mut F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let mut index = pos;
+    let size = items.len();
+    let half_size = size / 2;
+
+    while index < half_size {
+        let left_index = index * 2 + 1;
+        let right_index = left_index + 1;
+
+        // If the left or right node is smaller, swap with the smaller of those.
+        if compare(&items[left_index], &items[index]) == Ordering::Less {
+            if right_index < size
+                && compare(&items[right_index], &items[left_index]) == Ordering::Less
+            {
                 items.swap(index, right_index);
                 index = right_index;
             } else {
-                // Neither child is smaller. Exit.
-                return;
+                items.swap(index, left_index);
+                index = left_index;
             }
+        } else if right_index < size
+            && compare(&items[right_index], &items[index]) == Ordering::Less
+        {
+            items.swap(index, right_index);
+            index = right_index;
+        } else {
+            // Neither child is smaller. Exit.
+            return;
         }
     }
+}
 
-    fn rebuild(&mut self) {
-        let mut index = self.len() / 2;
+/// Rearranges `items[]` into a min-heap according to `compare`, bottom-up (Floyd).
+pub(crate) fn heapify_by<T, F>(items: &mut [T], compare: &mut F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let mut index = items.len() / 2;
 
-        while index > 0 {
-            index -= 1;
-            self.sift_down(index);
-        }
+    while index > 0 {
+        index -= 1;
+        sift_down_by(items, index, compare);
     }
 }
 
@@ -0,0 +1,248 @@
+//! # Distribution Sort (Counting Sort and Bucket Sort)
+//!
+//! This is adapted from
+//!
+//! <https://en.wikipedia.org/wiki/Counting_sort>
+//!
+//! <https://en.wikipedia.org/wiki/Bucket_sort>
+//!
+//! Test case is derived from
+//!
+//! <https://github.com/rust-lang/rust/blob/60bd3f96779dbe6bd206dae09395e9af7d580552/library/alloc/src/collections/binary_heap/tests.rs>
+
+use crate::merge_sort;
+use crate::radix_sort::gather;
+
+/// Sorting the entire array of small unsigned values, such as `u8` or `u16`,
+/// in `O(n + k)` time, where `k` is the largest value.
+pub fn counting_sort<T: Copy + Into<usize>>(a: &mut [T]) -> &mut [T] {
+    let buckets = a.iter().map(|&x| x.into() + 1).max().unwrap_or(0);
+    counting_sort_by_key(a, buckets, |&x| x.into())
+}
+
+/// Sorting the entire array by the key that `f` extracts from each element, in
+/// `O(n + buckets)` time. Every key must be less than `buckets`.
+///
+/// The sort is stable: elements with equal keys keep their original order,
+/// and `f` is called exactly once for every element.
+///
+/// # Panics
+///
+/// Panics if a key is not less than `buckets`.
+pub fn counting_sort_by_key<T, F>(a: &mut [T], buckets: usize, mut f: F) -> &mut [T]
+where
+    F: FnMut(&T) -> usize,
+{
+    let keys = a.iter().map(&mut f).collect::<Vec<usize>>();
+    let mut starts = vec![0usize; buckets];
+
+    for &k in &keys {
+        assert!(k < buckets, "key {k} is out of range 0..{buckets}");
+        starts[k] += 1;
+    }
+
+    // Turn the counts into the start of each bucket.
+    let mut start = 0;
+
+    for s in starts.iter_mut() {
+        let n = *s;
+        *s = start;
+        start += n;
+    }
+
+    // Scatter the indices, then move every element to its place at once.
+    let mut order = vec![0usize; a.len()];
+
+    for (i, &k) in keys.iter().enumerate() {
+        order[starts[k]] = i;
+        starts[k] += 1;
+    }
+
+    gather(a, &order);
+    a
+}
+
+/// Sorting the entire array of floats, which should be spread about uniformly
+/// between their least and greatest values, in `O(n)` expected time.
+///
+/// Each of the `n` buckets is sorted by [`merge_sort`], in the order of
+/// [`f64::total_cmp`]. Infinities and NaNs go into the first or last bucket.
+pub fn bucket_sort(a: &mut [f64]) -> &mut [f64] {
+    let size = a.len();
+
+    if size <= 1 {
+        return a;
+    }
+
+    let (min, max) = a
+        .iter()
+        .filter(|x| x.is_finite())
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), &x| {
+            (min.min(x), max.max(x))
+        });
+
+    // Halved, so the width of the range can not overflow.
+    let width = max / 2.0 - min / 2.0;
+    let mut buckets = vec![Vec::new(); size];
+
+    for &x in a.iter() {
+        let i = if !x.is_finite() {
+            if x.is_sign_negative() {
+                0
+            } else {
+                size - 1
+            }
+        } else if width > 0.0 {
+            let i = (x / 2.0 - min / 2.0) / width * size as f64;
+            (i as usize).min(size - 1)
+        } else {
+            0
+        };
+
+        buckets[i].push(x);
+    }
+
+    let mut begin = 0;
+
+    for mut bucket in buckets {
+        merge_sort::sort_by(&mut bucket, f64::total_cmp);
+        a[begin..begin + bucket.len()].copy_from_slice(&bucket);
+        begin += bucket.len();
+    }
+
+    a
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::distributions::Uniform;
+    use rand::Rng;
+
+    use super::*;
+
+    fn check_orderly<T: PartialOrd>(a: &[T]) {
+        if a.is_empty() {
+            return;
+        }
+
+        for i in 0..a.len() - 1 {
+            assert!(a[i] <= a[i + 1]);
+        }
+    }
+
+    #[test]
+    fn test_basic() {
+        let mut data = vec![5u8, 9, 3];
+        let sorted = vec![3, 5, 9];
+        counting_sort(&mut data);
+        check_orderly(&data);
+        assert_eq!(data, sorted);
+    }
+
+    #[test]
+    fn test_batch() {
+        check_orderly(counting_sort::<u8>(&mut []));
+        check_orderly(counting_sort(&mut [5u8]));
+        check_orderly(counting_sort(&mut [3u8, 2]));
+        check_orderly(counting_sort(&mut [2u8, 3]));
+        check_orderly(counting_sort(&mut [5u8, 1, 2]));
+        check_orderly(counting_sort(&mut [1u8, 100, 2, 3]));
+        check_orderly(counting_sort(&mut [1u8, 3, 5, 7, 9, 2, 4, 6, 8, 0]));
+        check_orderly(counting_sort(&mut [
+            2u8, 4, 6, 2, 1, 8, 10, 3, 5, 7, 0, 9, 1,
+        ]));
+        check_orderly(counting_sort(&mut [
+            9u8, 11, 9, 9, 9, 9, 11, 2, 3, 4, 11, 9, 0, 0, 0, 0,
+        ]));
+        check_orderly(counting_sort(&mut [0u16, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10]));
+        check_orderly(counting_sort(&mut [10u16, 9, 8, 7, 6, 5, 4, 3, 2, 1, 0]));
+        check_orderly(counting_sort(&mut [65535u16, 0, 1, 65535, 0]));
+    }
+
+    #[test]
+    fn test_counting_sort_by_key_stable() {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        enum Level {
+            Error,
+            Warn,
+            Info,
+        }
+
+        let data = [
+            (Level::Info, 0),
+            (Level::Error, 1),
+            (Level::Warn, 2),
+            (Level::Info, 3),
+            (Level::Error, 4),
+            (Level::Info, 5),
+        ];
+
+        let mut sorted = data.to_vec();
+        counting_sort_by_key(&mut sorted, 3, |x| x.0 as usize);
+        assert_eq!(
+            sorted.iter().map(|x| x.1).collect::<Vec<i32>>(),
+            vec![1, 4, 2, 0, 3, 5]
+        );
+
+        let mut rng = rand::thread_rng();
+        let range = Uniform::new_inclusive(0, 99);
+        let data = (&mut rng)
+            .sample_iter(range)
+            .take(10000)
+            .enumerate()
+            .map(|(i, x)| (x, i.to_string()))
+            .collect::<Vec<(usize, String)>>();
+
+        let mut sorted = data.clone();
+        sorted.sort_by_key(|x| x.0);
+
+        let mut data = data;
+        counting_sort_by_key(&mut data, 100, |x| x.0);
+        assert_eq!(data, sorted);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_counting_sort_out_of_range() {
+        counting_sort_by_key(&mut [1, 2, 3], 3, |&x| x);
+    }
+
+    #[test]
+    fn test_bucket_sort() {
+        let mut data = vec![0.42, 0.32, 0.23, 0.52, 0.25, 0.47, 0.51];
+        bucket_sort(&mut data);
+        assert_eq!(data, vec![0.23, 0.25, 0.32, 0.42, 0.47, 0.51, 0.52]);
+
+        let mut data = vec![1.0; 10];
+        bucket_sort(&mut data);
+        assert_eq!(data, vec![1.0; 10]);
+
+        let data = vec![
+            f64::NAN,
+            3.0,
+            f64::INFINITY,
+            -0.0,
+            f64::MAX,
+            f64::MIN,
+            -f64::NAN,
+            0.0,
+            f64::NEG_INFINITY,
+        ];
+        let mut sorted = data.clone();
+        sorted.sort_by(f64::total_cmp);
+        let bits = |a: &[f64]| a.iter().map(|x| x.to_bits()).collect::<Vec<u64>>();
+        assert_eq!(bits(bucket_sort(&mut data.clone())), bits(&sorted));
+    }
+
+    #[test]
+    fn test_bucket_sort_random() {
+        let mut rng = rand::thread_rng();
+        let range = Uniform::new(-1000.0, 1000.0);
+        let mut data = (&mut rng)
+            .sample_iter(range)
+            .take(50000)
+            .collect::<Vec<f64>>();
+        bucket_sort(&mut data);
+        check_orderly(&data);
+    }
+}
@@ -0,0 +1,301 @@
+//! The length-prefixed binary format of records.
+//!
+//! Every record is written as its length, a little-endian `u32`, followed by
+//! the bytes from [`Record::encode`].
+
+use std::io::{self, Read, Write};
+use std::marker::PhantomData;
+use std::mem;
+
+/// Values that can be written out to a run file and read back.
+pub trait Record: Sized {
+    /// Appends the bytes of the record to `out`.
+    fn encode(&self, out: &mut Vec<u8>);
+
+    /// Reads a record back from the bytes written by [`Record::encode`].
+    fn decode(bytes: &[u8]) -> io::Result<Self>;
+
+    /// The memory used by the record, counted against the memory budget.
+    fn size(&self) -> usize {
+        mem::size_of::<Self>()
+    }
+}
+
+macro_rules! impl_record_int {
+    ($($t:ty),*) => {$(
+        impl Record for $t {
+            fn encode(&self, out: &mut Vec<u8>) {
+                out.extend_from_slice(&self.to_le_bytes());
+            }
+
+            fn decode(bytes: &[u8]) -> io::Result<Self> {
+                bytes
+                    .try_into()
+                    .map(<$t>::from_le_bytes)
+                    .map_err(|_| invalid_data("wrong length for an integer record"))
+            }
+        }
+    )*};
+}
+
+impl_record_int!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+impl Record for Vec<u8> {
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(self);
+    }
+
+    fn decode(bytes: &[u8]) -> io::Result<Self> {
+        Ok(bytes.to_vec())
+    }
+
+    fn size(&self) -> usize {
+        mem::size_of::<Self>() + self.len()
+    }
+}
+
+impl Record for String {
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(self.as_bytes());
+    }
+
+    fn decode(bytes: &[u8]) -> io::Result<Self> {
+        String::from_utf8(bytes.to_vec()).map_err(|_| invalid_data("record is not UTF-8"))
+    }
+
+    fn size(&self) -> usize {
+        mem::size_of::<Self>() + self.len()
+    }
+}
+
+/// A pair is written as its first element with its own length prefix,
+/// followed by the second element.
+impl<A: Record, B: Record> Record for (A, B) {
+    fn encode(&self, out: &mut Vec<u8>) {
+        let start = out.len();
+        out.extend_from_slice(&[0; 4]);
+        self.0.encode(out);
+
+        let len = prefix(out.len() - start - 4);
+        out[start..start + 4].copy_from_slice(&len);
+        self.1.encode(out);
+    }
+
+    fn decode(bytes: &[u8]) -> io::Result<Self> {
+        let (len, rest) = bytes
+            .split_first_chunk::<4>()
+            .ok_or_else(|| invalid_data("truncated pair record"))?;
+        let len = u32::from_le_bytes(*len) as usize;
+
+        if len > rest.len() {
+            return Err(invalid_data("truncated pair record"));
+        }
+
+        let (a, b) = rest.split_at(len);
+        Ok((A::decode(a)?, B::decode(b)?))
+    }
+
+    fn size(&self) -> usize {
+        mem::size_of::<Self>() - mem::size_of::<A>() - mem::size_of::<B>()
+            + self.0.size()
+            + self.1.size()
+    }
+}
+
+/// Writes records to `W` in the length-prefixed format.
+#[derive(Debug)]
+pub struct RecordWriter<W: Write> {
+    writer: W,
+    buf: Vec<u8>,
+}
+
+impl<W: Write> RecordWriter<W> {
+    /// Creates a writer of records to `writer`, which should be buffered.
+    pub fn new(writer: W) -> Self {
+        RecordWriter {
+            writer,
+            buf: vec![],
+        }
+    }
+
+    /// Writes one record.
+    pub fn write<T: Record>(&mut self, record: &T) -> io::Result<()> {
+        self.buf.clear();
+        self.buf.extend_from_slice(&[0; 4]);
+        record.encode(&mut self.buf);
+
+        let len = prefix(self.buf.len() - 4);
+        self.buf[..4].copy_from_slice(&len);
+        self.writer.write_all(&self.buf)
+    }
+
+    /// Flushes the writer and returns it.
+    pub fn into_inner(mut self) -> io::Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// Reads records from `R` in the length-prefixed format, as an iterator.
+///
+/// The iterator ends after the first error.
+#[derive(Debug)]
+pub struct RecordReader<T, R: Read> {
+    reader: R,
+    buf: Vec<u8>,
+    done: bool,
+    marker: PhantomData<T>,
+}
+
+impl<T: Record, R: Read> RecordReader<T, R> {
+    /// Creates a reader of records from `reader`, which should be buffered.
+    pub fn new(reader: R) -> Self {
+        RecordReader {
+            reader,
+            buf: vec![],
+            done: false,
+            marker: PhantomData,
+        }
+    }
+
+    /// Reads the next record, or returns [`None`] at the end of the input.
+    fn read(&mut self) -> io::Result<Option<T>> {
+        let mut len = [0; 4];
+
+        if !fill(&mut self.reader, &mut len)? {
+            return Ok(None);
+        }
+
+        // The prefix is not trusted: the buffer only grows as the bytes arrive,
+        // so a corrupt length cannot make it allocate up to 4 GiB.
+        let len = u32::from_le_bytes(len) as u64;
+        self.buf.clear();
+
+        if (&mut self.reader).take(len).read_to_end(&mut self.buf)? as u64 != len {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        T::decode(&self.buf).map(Some)
+    }
+}
+
+impl<T: Record, R: Read> Iterator for RecordReader<T, R> {
+    type Item = io::Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let record = self.read().transpose();
+        self.done = !matches!(record, Some(Ok(_)));
+        record
+    }
+}
+
+/// Fills `buf` from `reader`. Returns `false` if the input ended before the
+/// first byte, and an error if it ended after that.
+fn fill<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<bool> {
+    let mut filled = 0;
+
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) if filled == 0 => return Ok(false),
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(true)
+}
+
+/// Returns the length prefix of a record `len` bytes long.
+///
+/// # Panics
+///
+/// Panics if the record is longer than `u32::MAX` bytes.
+fn prefix(len: usize) -> [u8; 4] {
+    u32::try_from(len)
+        .expect("record is longer than u32::MAX bytes")
+        .to_le_bytes()
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip<T: Record>(records: &[T]) -> Vec<T> {
+        let mut writer = RecordWriter::new(vec![]);
+
+        for record in records {
+            writer.write(record).unwrap();
+        }
+
+        let bytes = writer.into_inner().unwrap();
+        RecordReader::new(&bytes[..])
+            .collect::<io::Result<Vec<T>>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let data = vec![0u64, 1, u64::MAX, 42];
+        assert_eq!(round_trip(&data), data);
+
+        let data = vec![-5i32, i32::MIN, 7];
+        assert_eq!(round_trip(&data), data);
+
+        let data = vec![String::new(), "log line".to_string(), "ünïcode".to_string()];
+        assert_eq!(round_trip(&data), data);
+
+        let data = vec![(3u64, b"payload".to_vec()), (0, vec![]), (9, vec![0; 300])];
+        assert_eq!(round_trip(&data), data);
+
+        let data = vec![(String::from("key"), (1u8, String::from("nested")))];
+        assert_eq!(round_trip(&data), data);
+    }
+
+    #[test]
+    fn test_format() {
+        let mut writer = RecordWriter::new(vec![]);
+        writer.write(&0x0102_0304u32).unwrap();
+        writer.write(&String::from("ab")).unwrap();
+        assert_eq!(
+            writer.into_inner().unwrap(),
+            vec![4, 0, 0, 0, 4, 3, 2, 1, 2, 0, 0, 0, b'a', b'b']
+        );
+    }
+
+    #[test]
+    fn test_truncated() {
+        let bytes = [4, 0, 0, 0, 1, 2];
+        let mut reader = RecordReader::<u32, _>::new(&bytes[..]);
+        let error = reader.next().unwrap().unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+        assert!(reader.next().is_none());
+
+        let bytes = [4, 0];
+        let mut reader = RecordReader::<u32, _>::new(&bytes[..]);
+        let error = reader.next().unwrap().unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+
+        // A huge length with only a few bytes behind it.
+        let bytes = [0xff, 0xff, 0xff, 0xff, 1, 2, 3];
+        let mut reader = RecordReader::<Vec<u8>, _>::new(&bytes[..]);
+        let error = reader.next().unwrap().unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+        assert!(reader.buf.capacity() < 1 << 16);
+        assert!(reader.next().is_none());
+
+        let bytes = [2, 0, 0, 0, 1, 2];
+        let mut reader = RecordReader::<u32, _>::new(&bytes[..]);
+        let error = reader.next().unwrap().unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
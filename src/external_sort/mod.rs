@@ -0,0 +1,544 @@
+//! # External Merge Sort
+//!
+//! Sorts more records than fit in memory. The input is read in chunks that fit
+//! the memory budget, each chunk is sorted by [`crate::merge_sort`] and spilled
+//! to a temporary file as a sorted run, and the runs are merged with a
+//! [`BinaryHeap`], at most `fan_in` of them at a time.
+//!
+//! This is adapted from
+//!
+//! <https://en.wikipedia.org/wiki/External_sorting>
+//!
+//! <https://en.wikipedia.org/wiki/K-way_merge_algorithm>
+//!
+//! Test case is derived from
+//!
+//! <https://github.com/rust-lang/rust/blob/60bd3f96779dbe6bd206dae09395e9af7d580552/library/alloc/src/collections/binary_heap/tests.rs>
+
+mod codec;
+mod run;
+
+use std::cmp::Ordering;
+use std::env;
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+use std::vec;
+
+use crate::binary_heap::BinaryHeap;
+use crate::merge_sort;
+
+pub use codec::{Record, RecordReader, RecordWriter};
+use run::{RunFile, RunReader};
+
+/// Options for the external sorts.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExternalSortOptions {
+    /// Bytes of records, as counted by [`Record::size`], to hold in memory
+    /// before a chunk is sorted and spilled. Sorting a chunk needs about as
+    /// much memory again.
+    pub memory_budget: usize,
+    /// Directory for the temporary run files.
+    pub temp_dir: PathBuf,
+    /// Number of runs merged at once. More runs are merged in several passes.
+    pub fan_in: usize,
+}
+
+impl Default for ExternalSortOptions {
+    /// Creates options with a 64 MiB budget, spilling to the system temporary
+    /// directory and merging 16 runs at once.
+    fn default() -> Self {
+        ExternalSortOptions {
+            memory_budget: 64 << 20,
+            temp_dir: env::temp_dir(),
+            fan_in: 16,
+        }
+    }
+}
+
+/// Sorting the length-prefixed records read from `input`, and writing them to
+/// `output` in the same format.
+///
+/// The sort is stable: equal records keep their original order.
+pub fn sort<T, R, W>(input: R, output: W, options: &ExternalSortOptions) -> io::Result<()>
+where
+    T: Record + Ord,
+    R: Read,
+    W: Write,
+{
+    sort_reader::<T, R>(input, options)?.write_to(output)
+}
+
+/// Sorting the length-prefixed records read from `input`, returning an iterator
+/// over them in order.
+pub fn sort_reader<T, R>(input: R, options: &ExternalSortOptions) -> io::Result<SortedIter<T>>
+where
+    T: Record + Ord,
+    R: Read,
+{
+    external_sort(RecordReader::new(input), options)
+}
+
+/// Sorting the records of `input`, returning an iterator over them in order.
+pub fn sort_iter<T, I>(input: I, options: &ExternalSortOptions) -> io::Result<SortedIter<T>>
+where
+    T: Record + Ord,
+    I: IntoIterator<Item = T>,
+{
+    external_sort(input.into_iter().map(Ok), options)
+}
+
+/// The sorted records, either still in memory or merged from the run files as
+/// they are read.
+///
+/// The run files are removed once the iterator is dropped. If reading a run
+/// file fails, the records read before it are returned, then the error, and
+/// then the iterator ends.
+pub struct SortedIter<T> {
+    inner: Inner<T>,
+}
+
+enum Inner<T> {
+    Memory(vec::IntoIter<T>),
+    Merge(Merge<T>),
+}
+
+impl<T: Record + Ord> SortedIter<T> {
+    /// Writes all the remaining records to `output`, in the length-prefixed format.
+    pub fn write_to<W: Write>(self, output: W) -> io::Result<()> {
+        let mut writer = RecordWriter::new(output);
+
+        for record in self {
+            writer.write(&record?)?;
+        }
+
+        writer.into_inner()?;
+        Ok(())
+    }
+}
+
+impl<T: Record + Ord> Iterator for SortedIter<T> {
+    type Item = io::Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.inner {
+            Inner::Memory(records) => records.next().map(Ok),
+            Inner::Merge(merge) => merge.next(),
+        }
+    }
+}
+
+/// Reads `input` in chunks up to the memory budget, spills each sorted chunk as
+/// a run, then merges the runs down to at most `fan_in` of them.
+fn external_sort<T, I>(input: I, options: &ExternalSortOptions) -> io::Result<SortedIter<T>>
+where
+    T: Record + Ord,
+    I: Iterator<Item = io::Result<T>>,
+{
+    let fan_in = options.fan_in;
+
+    if fan_in < 2 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "fan-in must be at least 2",
+        ));
+    }
+
+    let mut chunk = vec![];
+    let mut used = 0;
+    let mut runs = vec![];
+
+    for record in input {
+        let record = record?;
+        used += record.size();
+        chunk.push(record);
+
+        if used >= options.memory_budget {
+            runs.push(spill(&mut chunk, options)?);
+            used = 0;
+        }
+    }
+
+    // Everything fits in memory, there is nothing to merge.
+    if runs.is_empty() {
+        merge_sort::sort(&mut chunk);
+
+        return Ok(SortedIter {
+            inner: Inner::Memory(chunk.into_iter()),
+        });
+    }
+
+    if !chunk.is_empty() {
+        runs.push(spill(&mut chunk, options)?);
+    }
+
+    // Merging neighbouring runs keeps equal records in their original order.
+    while runs.len() > fan_in {
+        let mut merged = vec![];
+        let mut rest = runs.into_iter().peekable();
+
+        while rest.peek().is_some() {
+            let group = rest.by_ref().take(fan_in).collect::<Vec<RunFile>>();
+
+            if group.len() == 1 {
+                merged.extend(group);
+            } else {
+                merged.push(run::write_run(&options.temp_dir, Merge::<T>::new(group)?)?);
+            }
+        }
+
+        runs = merged;
+    }
+
+    Ok(SortedIter {
+        inner: Inner::Merge(Merge::new(runs)?),
+    })
+}
+
+/// Sorts the chunk and writes it out as a run, leaving the chunk empty.
+fn spill<T>(chunk: &mut Vec<T>, options: &ExternalSortOptions) -> io::Result<RunFile>
+where
+    T: Record + Ord,
+{
+    merge_sort::sort(chunk);
+    run::write_run(&options.temp_dir, chunk.drain(..).map(Ok))
+}
+
+/// The next record of a run, ordered by the record, then by the index of the
+/// run, so equal records come out of earlier runs first.
+struct Entry<T> {
+    record: T,
+    run: usize,
+}
+
+impl<T: Ord> Ord for Entry<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.record
+            .cmp(&other.record)
+            .then(self.run.cmp(&other.run))
+    }
+}
+
+impl<T: Ord> PartialOrd for Entry<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: Ord> PartialEq for Entry<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T: Ord> Eq for Entry<T> {}
+
+/// Merges sorted runs, holding only the next record of each in the heap.
+///
+/// If reading a run fails, the record already taken from the heap is still
+/// returned, then the error, and then nothing more: the rest of the merge
+/// would be missing records.
+struct Merge<T> {
+    heap: BinaryHeap<Entry<T>>,
+    runs: Vec<RunReader<T>>,
+    error: Option<io::Error>,
+}
+
+impl<T: Record + Ord> Merge<T> {
+    fn new(files: Vec<RunFile>) -> io::Result<Self> {
+        let mut heap = BinaryHeap::new();
+        let mut runs = Vec::with_capacity(files.len());
+
+        for (run, file) in files.into_iter().enumerate() {
+            let mut reader = file.open()?;
+
+            if let Some(record) = reader.next().transpose()? {
+                heap.push(Entry { record, run });
+            }
+
+            runs.push(reader);
+        }
+
+        Ok(Merge {
+            heap,
+            runs,
+            error: None,
+        })
+    }
+}
+
+impl<T: Record + Ord> Iterator for Merge<T> {
+    type Item = io::Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(e) = self.error.take() {
+            return Some(Err(e));
+        }
+
+        let Entry { record, run } = self.heap.pop()?;
+
+        match self.runs[run].next() {
+            Some(Ok(next)) => self.heap.push(Entry { record: next, run }),
+            Some(Err(e)) => {
+                self.heap = BinaryHeap::new();
+                self.error = Some(e);
+            }
+            None => {}
+        }
+
+        Some(Ok(record))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::process;
+
+    use rand::distributions::Uniform;
+    use rand::Rng;
+
+    use super::*;
+
+    fn check_orderly<T: Ord>(a: &[T]) {
+        if a.is_empty() {
+            return;
+        }
+
+        for i in 0..a.len() - 1 {
+            assert!(a[i] <= a[i + 1]);
+        }
+    }
+
+    /// Options that spill every few records, in a fresh directory of their own.
+    fn small_options(name: &str, memory_budget: usize, fan_in: usize) -> ExternalSortOptions {
+        let temp_dir = env::temp_dir().join(format!("rust-dsa-test-{}-{name}", process::id()));
+        fs::create_dir_all(&temp_dir).unwrap();
+
+        ExternalSortOptions {
+            memory_budget,
+            temp_dir,
+            fan_in,
+        }
+    }
+
+    /// Checks that all the run files were removed, then removes the directory.
+    fn check_cleaned_up(options: &ExternalSortOptions) {
+        assert_eq!(fs::read_dir(&options.temp_dir).unwrap().count(), 0);
+        fs::remove_dir(&options.temp_dir).unwrap();
+    }
+
+    /// A record ordered by its key alone.
+    #[derive(Debug, Clone)]
+    struct Line {
+        key: u8,
+        id: u32,
+    }
+
+    impl Record for Line {
+        fn encode(&self, out: &mut Vec<u8>) {
+            out.push(self.key);
+            self.id.encode(out);
+        }
+
+        fn decode(bytes: &[u8]) -> io::Result<Self> {
+            Ok(Line {
+                key: bytes[0],
+                id: u32::decode(&bytes[1..])?,
+            })
+        }
+    }
+
+    impl Ord for Line {
+        fn cmp(&self, other: &Self) -> Ordering {
+            self.key.cmp(&other.key)
+        }
+    }
+
+    impl PartialOrd for Line {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl PartialEq for Line {
+        fn eq(&self, other: &Self) -> bool {
+            self.key == other.key
+        }
+    }
+
+    impl Eq for Line {}
+
+    #[test]
+    fn test_basic() {
+        let data = vec![5u32, 9, 3];
+        let sorted = sort_iter(data, &ExternalSortOptions::default())
+            .unwrap()
+            .collect::<io::Result<Vec<u32>>>()
+            .unwrap();
+        assert_eq!(sorted, vec![3, 5, 9]);
+    }
+
+    #[test]
+    fn test_batch() {
+        let options = small_options("batch", 16, 2);
+
+        for data in [
+            vec![],
+            vec![5],
+            vec![3, 2],
+            vec![2, 3],
+            vec![5, 1, 2],
+            vec![1, 100, 2, 3],
+            vec![1, 3, 5, 7, 9, 2, 4, 6, 8, 0],
+            vec![2, 4, 6, 2, 1, 8, 10, 3, 5, 7, 0, 9, 1],
+            vec![9, 11, 9, 9, 9, 9, 11, 2, 3, 4, 11, 9, 0, 0, 0, 0],
+            vec![10, 9, 8, 7, 6, 5, 4, 3, 2, 1, 0],
+        ] {
+            let sorted = sort_iter::<i64, _>(data, &options)
+                .unwrap()
+                .collect::<io::Result<Vec<i64>>>()
+                .unwrap();
+            check_orderly(&sorted);
+        }
+
+        check_cleaned_up(&options);
+    }
+
+    #[test]
+    fn test_spilled_stable() {
+        // A handful of records per run and a fan-in of 3, for several merge passes.
+        let options = small_options("stable", 100, 3);
+        let mut rng = rand::thread_rng();
+        let range = Uniform::new_inclusive(0, 20);
+        let data = (&mut rng)
+            .sample_iter(range)
+            .take(5000)
+            .enumerate()
+            .map(|(i, key)| Line { key, id: i as u32 })
+            .collect::<Vec<Line>>();
+
+        let mut expected = data.clone();
+        expected.sort();
+
+        let sorted = sort_iter(data, &options)
+            .unwrap()
+            .collect::<io::Result<Vec<Line>>>()
+            .unwrap();
+        assert_eq!(
+            sorted
+                .iter()
+                .map(|x| (x.key, x.id))
+                .collect::<Vec<(u8, u32)>>(),
+            expected
+                .iter()
+                .map(|x| (x.key, x.id))
+                .collect::<Vec<(u8, u32)>>()
+        );
+
+        check_cleaned_up(&options);
+    }
+
+    #[test]
+    fn test_read_write() {
+        let options = small_options("read_write", 256, 4);
+        let mut rng = rand::thread_rng();
+        let range = Uniform::new_inclusive(0, 1_000_000);
+        let data = (&mut rng)
+            .sample_iter(range)
+            .take(3000)
+            .map(|x: u64| (x, format!("event {x}")))
+            .collect::<Vec<(u64, String)>>();
+
+        let mut input = RecordWriter::new(vec![]);
+
+        for record in &data {
+            input.write(record).unwrap();
+        }
+
+        let input = input.into_inner().unwrap();
+        let mut output = vec![];
+        sort::<(u64, String), _, _>(&input[..], &mut output, &options).unwrap();
+
+        let sorted = RecordReader::new(&output[..])
+            .collect::<io::Result<Vec<(u64, String)>>>()
+            .unwrap();
+        let mut expected = data;
+        expected.sort();
+        assert_eq!(sorted, expected);
+
+        check_cleaned_up(&options);
+    }
+
+    #[test]
+    fn test_dropped_early() {
+        let options = small_options("dropped", 64, 2);
+        let mut sorted = sort_iter((0..1000u32).rev(), &options).unwrap();
+        assert_eq!(sorted.next().unwrap().unwrap(), 0);
+        assert_eq!(sorted.next().unwrap().unwrap(), 1);
+        drop(sorted);
+
+        check_cleaned_up(&options);
+    }
+
+    #[test]
+    fn test_truncated_run() {
+        let options = small_options("truncated", 0, 2);
+        let size = 5000u32;
+
+        // Two interleaved runs, long enough that the truncated tail is not
+        // already buffered when the merge starts.
+        let evens = run::write_run(&options.temp_dir, (0..size).map(|x| Ok(x * 2))).unwrap();
+        let path = fs::read_dir(&options.temp_dir)
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap()
+            .path();
+        let odds = run::write_run(&options.temp_dir, (0..size).map(|x| Ok(x * 2 + 1))).unwrap();
+
+        // Keep 3000 whole records of 8 bytes, and half of the next one's prefix.
+        let kept = 3000;
+        fs::OpenOptions::new()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_len(kept * 8 + 2)
+            .unwrap();
+
+        let mut merge = Merge::<u32>::new(vec![evens, odds]).unwrap();
+        let mut records = vec![];
+
+        let error = loop {
+            match merge.next() {
+                Some(Ok(record)) => records.push(record),
+                Some(Err(e)) => break e,
+                None => panic!("the truncated run was not reported"),
+            }
+        };
+
+        // Every record taken from the heap is returned before the error, and
+        // nothing comes after it.
+        assert_eq!(records, (0..kept as u32 * 2 - 1).collect::<Vec<u32>>());
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+        assert!(merge.next().is_none());
+        assert!(merge.next().is_none());
+
+        drop(merge);
+        check_cleaned_up(&options);
+    }
+
+    #[test]
+    fn test_errors() {
+        let options = ExternalSortOptions {
+            fan_in: 1,
+            ..ExternalSortOptions::default()
+        };
+        let error = sort_iter(vec![1u8], &options).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+
+        let truncated = [4, 0, 0, 0, 1, 0, 0, 0, 4, 0, 0];
+        let error =
+            sort::<u32, _, _>(&truncated[..], vec![], &ExternalSortOptions::default()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
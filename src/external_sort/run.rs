@@ -0,0 +1,87 @@
+//! Sorted runs, spilled to temporary files.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use super::codec::{Record, RecordReader, RecordWriter};
+
+/// Numbers the run files of this process.
+static NEXT_RUN: AtomicUsize = AtomicUsize::new(0);
+
+/// A temporary file holding one sorted run, which is removed when dropped.
+#[derive(Debug)]
+pub(super) struct RunFile {
+    path: PathBuf,
+}
+
+impl RunFile {
+    /// Creates a new, empty run file in `dir`.
+    fn create(dir: &Path) -> io::Result<(RunFile, File)> {
+        loop {
+            let name = format!(
+                "rust-dsa-{}-{}.run",
+                process::id(),
+                NEXT_RUN.fetch_add(1, Ordering::Relaxed)
+            );
+            let path = dir.join(name);
+
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(file) => return Ok((RunFile { path }, file)),
+                // Left behind by an earlier process with the same id, try the next name.
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Opens the run to read its records back, in order.
+    pub(super) fn open<T: Record>(self) -> io::Result<RunReader<T>> {
+        let file = File::open(&self.path)?;
+
+        Ok(RunReader {
+            records: RecordReader::new(BufReader::new(file)),
+            _file: self,
+        })
+    }
+}
+
+impl Drop for RunFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Reads the records of a run file, which is removed once the reader is dropped.
+pub(super) struct RunReader<T> {
+    // Declared first, so the file is closed before it is removed.
+    records: RecordReader<T, BufReader<File>>,
+    _file: RunFile,
+}
+
+impl<T: Record> Iterator for RunReader<T> {
+    type Item = io::Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.records.next()
+    }
+}
+
+/// Writes the already sorted `records` to a new run file in `dir`.
+pub(super) fn write_run<T, I>(dir: &Path, records: I) -> io::Result<RunFile>
+where
+    T: Record,
+    I: IntoIterator<Item = io::Result<T>>,
+{
+    let (run, file) = RunFile::create(dir)?;
+    let mut writer = RecordWriter::new(BufWriter::new(file));
+
+    for record in records {
+        writer.write(&record?)?;
+    }
+
+    writer.into_inner()?;
+    Ok(run)
+}
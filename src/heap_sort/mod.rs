@@ -0,0 +1,152 @@
+//! # Heap Sort
+//!
+//! This is adapted from
+//!
+//! <https://en.wikipedia.org/wiki/Heapsort>
+//!
+//! The sift logic is shared with [`crate::binary_heap`].
+//!
+//! Test case is derived from
+//!
+//! <https://github.com/rust-lang/rust/blob/60bd3f96779dbe6bd206dae09395e9af7d580552/library/alloc/src/collections/binary_heap/tests.rs>
+
+use std::cmp::Ordering;
+
+use crate::binary_heap;
+
+/// Sorting the entire array in place, in `O(n log n)` time and `O(1)` extra memory.
+pub fn sort<T: Ord>(a: &mut [T]) -> &mut [T] {
+    sort_by(a, T::cmp)
+}
+
+/// Sorting the entire array in place with a comparator function.
+pub fn sort_by<T, F>(a: &mut [T], mut compare: F) -> &mut [T]
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    heap_sort(a, &mut compare);
+    a
+}
+
+/// Sorting the entire array in place with a key extraction function.
+pub fn sort_by_key<T, K, F>(a: &mut [T], mut f: F) -> &mut [T]
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    sort_by(a, |x, y| f(x).cmp(&f(y)))
+}
+
+/// Builds a max-heap in `a[]`, then repeatedly moves its largest item behind
+/// the shrinking heap.
+pub(crate) fn heap_sort<T, F>(a: &mut [T], compare: &mut F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    // A max-heap is a min-heap under the reversed order.
+    let mut reversed = |x: &T, y: &T| compare(y, x);
+
+    binary_heap::heapify_by(a, &mut reversed);
+
+    for end in (1..a.len()).rev() {
+        a.swap(0, end);
+        binary_heap::sift_down_by(&mut a[..end], 0, &mut reversed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::distributions::Uniform;
+    use rand::Rng;
+
+    use super::*;
+
+    fn check_orderly<T: Ord>(a: &[T]) {
+        if a.is_empty() {
+            return;
+        }
+
+        for i in 0..a.len() - 1 {
+            assert!(a[i] <= a[i + 1]);
+        }
+    }
+
+    #[test]
+    fn test_basic() {
+        let mut data = vec![5, 9, 3];
+        let sorted = vec![3, 5, 9];
+        sort(&mut data);
+        check_orderly(&data);
+        assert_eq!(data, sorted);
+    }
+
+    #[test]
+    fn test_normal() {
+        let mut data = vec![2, 4, 6, 2, 1, 8, 10, 3, 5, 7, 0, 9, 1];
+        let sorted = vec![0, 1, 1, 2, 2, 3, 4, 5, 6, 7, 8, 9, 10];
+        sort(&mut data);
+        check_orderly(&data);
+        assert_eq!(data, sorted);
+    }
+
+    #[test]
+    fn test_batch() {
+        check_orderly(sort::<i32>(&mut []));
+        check_orderly(sort(&mut [5]));
+        check_orderly(sort(&mut [3, 2]));
+        check_orderly(sort(&mut [2, 3]));
+        check_orderly(sort(&mut [5, 1, 2]));
+        check_orderly(sort(&mut [1, 100, 2, 3]));
+        check_orderly(sort(&mut [1, 3, 5, 7, 9, 2, 4, 6, 8, 0]));
+        check_orderly(sort(&mut [2, 4, 6, 2, 1, 8, 10, 3, 5, 7, 0, 9, 1]));
+        check_orderly(sort(&mut [
+            9, 11, 9, 9, 9, 9, 11, 2, 3, 4, 11, 9, 0, 0, 0, 0,
+        ]));
+        check_orderly(sort(&mut [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10]));
+        check_orderly(sort(&mut [10, 9, 8, 7, 6, 5, 4, 3, 2, 1, 0]));
+        check_orderly(sort(&mut [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 0, 0, 0, 1, 2]));
+        check_orderly(sort(&mut [5, 4, 3, 2, 1, 5, 4, 3, 2, 1, 5, 4, 3, 2, 1]));
+    }
+
+    #[test]
+    fn test_sort_by() {
+        let mut data = vec![-10, 1, 2, 3, 3, -20, 5, 43];
+        sort_by(&mut data, |x, y| y.cmp(x));
+        assert_eq!(data, vec![43, 5, 3, 3, 2, 1, -10, -20]);
+
+        sort_by_key(&mut data, |x: &i32| x.abs());
+        assert_eq!(data, vec![1, 2, 3, 3, 5, -10, -20, 43]);
+    }
+
+    #[test]
+    fn test_worst_case() {
+        let size = 10000;
+        let bound = 2 * size * 14;
+
+        for mut data in [
+            (0..size).collect::<Vec<usize>>(),
+            (0..size).rev().collect::<Vec<usize>>(),
+            (0..size).map(|i| i % 7).collect::<Vec<usize>>(),
+        ] {
+            let mut count = 0;
+            sort_by(&mut data, |x, y| {
+                count += 1;
+                x.cmp(y)
+            });
+            check_orderly(&data);
+            assert!(count < bound);
+        }
+    }
+
+    #[test]
+    fn test_random_large() {
+        let mut rng = rand::thread_rng();
+        let range = Uniform::new_inclusive(-50000, 50000);
+        let mut data = (&mut rng)
+            .sample_iter(range)
+            .take(50000)
+            .collect::<Vec<i32>>();
+        sort(&mut data);
+        check_orderly(&data);
+    }
+}
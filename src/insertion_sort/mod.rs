@@ -0,0 +1,199 @@
+//! # Insertion Sort
+//!
+//! This is adapted from
+//!
+//! <https://en.wikipedia.org/wiki/Insertion_sort>
+//!
+//! <https://en.wikipedia.org/wiki/Insertion_sort#Variants>
+//!
+//! Test case is derived from
+//!
+//! <https://github.com/rust-lang/rust/blob/60bd3f96779dbe6bd206dae09395e9af7d580552/library/alloc/src/collections/binary_heap/tests.rs>
+
+use std::cmp::Ordering;
+
+/// Options for the recursive sorts, [`crate::quick_sort`] and [`crate::merge_sort`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SortOptions {
+    /// Partitions, or runs, up to this length are insertion sorted instead of
+    /// being divided any further.
+    pub insertion_threshold: usize,
+}
+
+impl Default for SortOptions {
+    /// Creates the options used by the plain `sort` functions.
+    fn default() -> Self {
+        SortOptions {
+            insertion_threshold: 16,
+        }
+    }
+}
+
+/// Sorting the entire array.
+pub fn sort<T: Ord>(a: &mut [T]) -> &mut [T] {
+    sort_by(a, T::cmp)
+}
+
+/// Sorting the entire array with a comparator function.
+///
+/// The sort is stable: equal elements keep their original order.
+pub fn sort_by<T, F>(a: &mut [T], compare: F) -> &mut [T]
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    insertion_sort(a, compare)
+}
+
+/// Sorting the entire array, finding each insertion point by binary search.
+pub fn binary_sort<T: Ord>(a: &mut [T]) -> &mut [T] {
+    binary_sort_by(a, T::cmp)
+}
+
+/// Sorting the entire array with a comparator function, finding each
+/// insertion point by binary search.
+///
+/// The sort is stable: equal elements keep their original order.
+pub fn binary_sort_by<T, F>(a: &mut [T], compare: F) -> &mut [T]
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    binary_insertion_sort(a, 1, compare)
+}
+
+/// Sorting the entire array with a comparator function, moving each element
+/// left past all the elements that are greater than it.
+///
+/// The sort is stable: equal elements keep their original order.
+pub fn insertion_sort<T, F>(a: &mut [T], mut compare: F) -> &mut [T]
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    for i in 1..a.len() {
+        let mut j = i;
+
+        while j > 0 && compare(&a[j], &a[j - 1]) == Ordering::Less {
+            a.swap(j, j - 1);
+            j -= 1;
+        }
+    }
+
+    a
+}
+
+/// Sorting the entire array with a comparator function, when its first
+/// `sorted` items are already in order, by inserting each remaining item after
+/// all the items that are not greater than it.
+///
+/// The sort is stable: equal elements keep their original order.
+pub fn binary_insertion_sort<T, F>(a: &mut [T], sorted: usize, mut compare: F) -> &mut [T]
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    for i in sorted.max(1)..a.len() {
+        let (head, tail) = a.split_at(i);
+        let pos = head.partition_point(|x| compare(&tail[0], x) != Ordering::Less);
+        a[pos..=i].rotate_right(1);
+    }
+
+    a
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::distributions::Uniform;
+    use rand::Rng;
+
+    use super::*;
+
+    fn check_orderly<T: Ord>(a: &[T]) {
+        if a.is_empty() {
+            return;
+        }
+
+        for i in 0..a.len() - 1 {
+            assert!(a[i] <= a[i + 1]);
+        }
+    }
+
+    #[test]
+    fn test_basic() {
+        let mut data = vec![5, 9, 3];
+        let sorted = vec![3, 5, 9];
+        sort(&mut data);
+        check_orderly(&data);
+        assert_eq!(data, sorted);
+
+        let mut data = vec![5, 9, 3];
+        binary_sort(&mut data);
+        assert_eq!(data, sorted);
+    }
+
+    #[test]
+    fn test_batch() {
+        for sorter in [sort::<i32>, binary_sort::<i32>] {
+            check_orderly(sorter(&mut []));
+            check_orderly(sorter(&mut [5]));
+            check_orderly(sorter(&mut [3, 2]));
+            check_orderly(sorter(&mut [2, 3]));
+            check_orderly(sorter(&mut [5, 1, 2]));
+            check_orderly(sorter(&mut [1, 100, 2, 3]));
+            check_orderly(sorter(&mut [1, 3, 5, 7, 9, 2, 4, 6, 8, 0]));
+            check_orderly(sorter(&mut [2, 4, 6, 2, 1, 8, 10, 3, 5, 7, 0, 9, 1]));
+            check_orderly(sorter(&mut [
+                9, 11, 9, 9, 9, 9, 11, 2, 3, 4, 11, 9, 0, 0, 0, 0,
+            ]));
+            check_orderly(sorter(&mut [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10]));
+            check_orderly(sorter(&mut [10, 9, 8, 7, 6, 5, 4, 3, 2, 1, 0]));
+            check_orderly(sorter(&mut [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 0, 0, 0, 1, 2]));
+            check_orderly(sorter(&mut [5, 4, 3, 2, 1, 5, 4, 3, 2, 1, 5, 4, 3, 2, 1]));
+        }
+    }
+
+    #[test]
+    fn test_insertion_sort_by() {
+        let mut data = vec![(2, 'a'), (1, 'b'), (2, 'c'), (1, 'd')];
+        insertion_sort(&mut data, |x, y| x.0.cmp(&y.0));
+        assert_eq!(data, vec![(1, 'b'), (1, 'd'), (2, 'a'), (2, 'c')]);
+
+        // The sorted prefix is trusted, only the items after it are inserted.
+        let mut data = vec![1, 4, 7, 9, 3, 8, 1];
+        binary_insertion_sort(&mut data, 4, i32::cmp);
+        assert_eq!(data, vec![1, 1, 3, 4, 7, 8, 9]);
+
+        let mut data = vec![(1, 'a'), (3, 'b'), (1, 'c'), (3, 'd')];
+        binary_insertion_sort(&mut data, 0, |x, y| x.0.cmp(&y.0));
+        assert_eq!(data, vec![(1, 'a'), (1, 'c'), (3, 'b'), (3, 'd')]);
+    }
+
+    #[test]
+    fn test_stable() {
+        let mut rng = rand::thread_rng();
+        let range = Uniform::new_inclusive(0, 10);
+        let data = (&mut rng)
+            .sample_iter(range)
+            .take(500)
+            .enumerate()
+            .map(|(i, x)| (x, i))
+            .collect::<Vec<(i32, usize)>>();
+
+        let mut linear = data.clone();
+        sort_by(&mut linear, |x, y| x.0.cmp(&y.0));
+        check_orderly(&linear);
+
+        let mut binary = data;
+        binary_sort_by(&mut binary, |x, y| x.0.cmp(&y.0));
+        check_orderly(&binary);
+    }
+
+    #[test]
+    fn test_random() {
+        let mut rng = rand::thread_rng();
+        let range = Uniform::new_inclusive(-1000, 1000);
+        let mut data = (&mut rng)
+            .sample_iter(range)
+            .take(1000)
+            .collect::<Vec<i32>>();
+        binary_sort(&mut data);
+        check_orderly(&data);
+    }
+}
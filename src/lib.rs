@@ -1,4 +1,17 @@
 pub mod binary_heap;
+pub mod distribution_sort;
+pub mod external_sort;
+pub mod heap_sort;
+pub mod insertion_sort;
 pub mod merge_sort;
+pub mod multikey_quick_sort;
+pub mod pdqsort;
+pub mod permutation;
 pub mod quick_sort;
+pub mod radix_sort;
+pub mod sort_spec;
 pub mod stack;
+pub mod tim_sort;
+
+/// The default unstable sort.
+pub use pdqsort::{sort as sort_unstable, sort_by as sort_unstable_by};
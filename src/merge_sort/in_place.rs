@@ -0,0 +1,781 @@
+//! Stable merge sort in place, with `O(1)` extra memory.
+//!
+//! This follows GrailSort: the first occurrences of about `2√n` distinct values
+//! are collected at the front of the array. Some of them serve as a buffer that
+//! the merges swap elements through, the others tag the blocks of a block merge
+//! so equal blocks keep their original order. At the end the collected values
+//! are sorted and merged back with the rest.
+//!
+//! With fewer distinct values there are too few keys for a buffer as long as
+//! the blocks. The longest runs are then merged block by block with rotations,
+//! which stays cheap because so few distinct values leave few places where the
+//! runs interleave.
+//!
+//! <https://github.com/Mrrl/GrailSort>
+//!
+//! <https://en.wikipedia.org/wiki/Block_sort>
+
+use std::cmp::Ordering;
+use std::mem::MaybeUninit;
+
+use super::merge;
+use super::sorted_slices::rotation_merge;
+use crate::heap_sort::heap_sort;
+use crate::insertion_sort::binary_insertion_sort;
+
+/// Length of the runs that are insertion sorted before any merging.
+const RUN: usize = 16;
+
+/// Sorting the entire array in place, stable, in `O(n log n)` time and `O(1)`
+/// extra memory.
+pub fn sort_in_place<T: Ord>(a: &mut [T]) -> &mut [T] {
+    sort_in_place_by(a, T::cmp)
+}
+
+/// Sorting the entire array in place with a comparator function, like
+/// [`sort_in_place`].
+///
+/// The sort is stable: equal elements keep their original order.
+pub fn sort_in_place_by<T, F>(a: &mut [T], compare: F) -> &mut [T]
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    sort_in_place_by_with_buffer(a, &mut [], compare)
+}
+
+/// Sorting the entire array in place, like [`sort_in_place`], moving elements
+/// through the caller's work array `buffer` where it is long enough.
+///
+/// Runs up to `buffer.len()` long are merged through it, so a buffer of `n / 2`
+/// items makes this an ordinary merge sort.
+pub fn sort_in_place_with_buffer<'a, T: Ord>(
+    a: &'a mut [T],
+    buffer: &mut [MaybeUninit<T>],
+) -> &'a mut [T] {
+    sort_in_place_by_with_buffer(a, buffer, T::cmp)
+}
+
+/// Sorting the entire array in place with a comparator function, like
+/// [`sort_in_place_with_buffer`].
+///
+/// The sort is stable: equal elements keep their original order.
+pub fn sort_in_place_by_with_buffer<'a, T, F>(
+    a: &'a mut [T],
+    buffer: &mut [MaybeUninit<T>],
+    mut compare: F,
+) -> &'a mut [T]
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    block_merge_sort(a, buffer, &mut compare);
+    a
+}
+
+/// Collects the keys, sorts the rest of the array with their help, then merges
+/// the keys back in.
+fn block_merge_sort<T, F>(a: &mut [T], ext: &mut [MaybeUninit<T>], compare: &mut F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let n = a.len();
+
+    if n <= RUN {
+        binary_insertion_sort(a, 1, compare);
+        return;
+    }
+
+    // The caller's buffer is enough for every merge, no keys are needed.
+    if ext.len() >= n / 2 {
+        sort_levels(a, 0, Layout::External, ext, compare);
+        return;
+    }
+
+    // Blocks of `block` items, a power of two, with `block * block >= n`.
+    let block = (n as f64).sqrt().ceil() as usize;
+    let block = block.next_power_of_two().max(RUN);
+    let tags = n.div_ceil(block);
+
+    let keys = collect_keys(a, block + tags, compare);
+
+    let layout = if keys == block + tags {
+        Layout::Blocks { block }
+    } else {
+        // Too few distinct values: half of the keys buffer the shorter runs,
+        // the other half tag the blocks of the longer ones.
+        let block = (keys / 2 + 1).next_power_of_two() / 2;
+        Layout::Blocks { block }
+    };
+
+    sort_levels(a, keys, layout, ext, compare);
+
+    // The keys are distinct, so any sort puts them back in their original order.
+    heap_sort(&mut a[..keys], compare);
+    rotation_merge(a, keys, compare);
+}
+
+/// How the merges of [`sort_levels`] use the keys in front of the array.
+#[derive(Clone, Copy, Debug)]
+enum Layout {
+    /// No keys, the caller's buffer is long enough for every merge.
+    External,
+    /// The last `block` keys are the buffer, the first ones tag the blocks of
+    /// longer runs. Runs too long for that many tags are merged by
+    /// [`lazy_level`].
+    Blocks { block: usize },
+}
+
+/// Sorts `a[keys..]` bottom-up: insertion sorts short runs, then merges pairs
+/// of runs of doubling width.
+fn sort_levels<T, F>(
+    a: &mut [T],
+    keys: usize,
+    layout: Layout,
+    ext: &mut [MaybeUninit<T>],
+    compare: &mut F,
+) where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let n = a.len();
+
+    for run in a[keys..].chunks_mut(RUN) {
+        binary_insertion_sort(run, 1, &mut *compare);
+    }
+
+    let buffer = match layout {
+        Layout::External => 0,
+        Layout::Blocks { block } => block,
+    };
+    let tags = keys - buffer;
+
+    let mut width = RUN;
+
+    while width < n - keys {
+        if width <= ext.len() {
+            // Moves through the caller's buffer are the cheapest.
+            let mut begin = keys;
+
+            while begin + width < n {
+                let middle = begin + width;
+                let end = (middle + width).min(n);
+                merge(a, begin, middle, end, ext, compare);
+                begin = end;
+            }
+        } else if width <= buffer {
+            buffer_level(a, keys, buffer, width, compare);
+        } else if 2 * width <= buffer * tags {
+            block_level(a, keys, buffer, width, compare);
+        } else {
+            lazy_level(a, keys, tags, width, compare);
+        }
+
+        width *= 2;
+    }
+}
+
+/// Merges pairs of runs up to `buffer` long, swapping them through the buffer
+/// `a[keys - buffer..keys]`, which ends up behind the last run and is moved
+/// back in front afterwards.
+fn buffer_level<T, F>(a: &mut [T], keys: usize, buffer: usize, width: usize, compare: &mut F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let n = a.len();
+    let mut begin = keys;
+
+    while begin < n {
+        let middle = (begin + width).min(n);
+        let end = (middle + width).min(n);
+        merge_left(a, begin - buffer, begin, middle, end, compare);
+        begin = end;
+    }
+
+    a[keys - buffer..].rotate_right(buffer);
+}
+
+/// Merges pairs of runs longer than the buffer `a[keys - block..keys]`, by
+/// sorting their blocks, then merging neighbouring blocks through the buffer.
+fn block_level<T, F>(a: &mut [T], keys: usize, block: usize, width: usize, compare: &mut F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let n = a.len();
+    let mut begin = keys;
+
+    while begin < n {
+        let middle = (begin + width).min(n);
+        let end = (middle + width).min(n);
+
+        if end - middle <= block {
+            // The right run fits the buffer, a plain merge will do.
+            merge_left(a, begin - block, begin, middle, end, compare);
+        } else {
+            // A right run that is not a whole number of blocks leaves a tail.
+            let tail = middle + (end - middle) / block * block;
+            let left_blocks = (middle - begin) / block;
+            let blocks = (tail - begin) / block;
+
+            let first_right = sort_blocks(a, begin, block, blocks, left_blocks, compare);
+            merge_blocks(a, begin, block, blocks, first_right, compare);
+            heap_sort(&mut a[..blocks], compare);
+
+            if tail < end {
+                // Swap the buffer past the tail, then merge the tail in by rotations.
+                merge_left(a, tail - block, tail, end, end, compare);
+                rotation_merge(&mut a[begin - block..end - block], tail - begin, compare);
+            }
+        }
+
+        begin = end;
+    }
+
+    a[keys - block..].rotate_right(block);
+}
+
+/// Selection sorts the `blocks` blocks of `block` items from `a[begin]` by their
+/// first items, then by their tags `a[..blocks]`, which are swapped along.
+///
+/// The first `left_blocks` blocks are of the left run, so their tags are the
+/// least ones, which keeps equal blocks of the left run first. Returns where
+/// the tag of the first block of the right run went.
+fn sort_blocks<T, F>(
+    a: &mut [T],
+    begin: usize,
+    block: usize,
+    blocks: usize,
+    left_blocks: usize,
+    compare: &mut F,
+) -> usize
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let mut first_right = left_blocks;
+
+    for i in 0..blocks {
+        let mut min = i;
+
+        for j in i + 1..blocks {
+            let order = compare(&a[begin + j * block], &a[begin + min * block])
+                .then_with(|| compare(&a[j], &a[min]));
+
+            if order == Ordering::Less {
+                min = j;
+            }
+        }
+
+        if min != i {
+            a.swap(i, min);
+
+            if first_right == i {
+                first_right = min;
+            } else if first_right == min {
+                first_right = i;
+            }
+
+            for k in 0..block {
+                a.swap(begin + i * block + k, begin + min * block + k);
+            }
+        }
+    }
+
+    first_right
+}
+
+/// Merges the sorted blocks from `a[begin]` into one run, through the buffer
+/// `a[begin - block..begin]`, which ends up behind them.
+///
+/// A block is of the left run if its tag is less than the tag `a[first_right]`
+/// of the first block of the right run.
+fn merge_blocks<T, F>(
+    a: &mut [T],
+    begin: usize,
+    block: usize,
+    blocks: usize,
+    first_right: usize,
+    compare: &mut F,
+) where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    // The buffer always sits right before the pending items `a[rest..rest_end]`,
+    // which all come from one run, and everything before the buffer is final.
+    let mut out = begin - block;
+    let mut rest = begin;
+    let mut rest_end = begin + block;
+    let mut rest_is_left = blocks > 0 && compare(&a[0], &a[first_right]) == Ordering::Less;
+
+    for i in 1..blocks {
+        let next = begin + i * block;
+        let next_end = next + block;
+        let next_is_left = compare(&a[i], &a[first_right]) == Ordering::Less;
+
+        if next_is_left == rest_is_left {
+            // Nothing after this can come before the pending items.
+            while rest < rest_end {
+                a.swap(out, rest);
+                out += 1;
+                rest += 1;
+            }
+
+            rest = next;
+            rest_end = next_end;
+            continue;
+        }
+
+        let mut i = rest;
+        let mut j = next;
+
+        // Take from the next block when it is less, or equal and of the left run.
+        while i < rest_end && j < next_end {
+            let order = compare(&a[j], &a[i]);
+            let take_next = order == Ordering::Less || (order == Ordering::Equal && next_is_left);
+
+            if take_next {
+                a.swap(out, j);
+                j += 1;
+            } else {
+                a.swap(out, i);
+                i += 1;
+            }
+
+            out += 1;
+        }
+
+        if i == rest_end {
+            // The pending items ran out, what is left of the next block is pending now.
+            rest = j;
+            rest_end = next_end;
+            rest_is_left = next_is_left;
+        } else {
+            // The next block ran out, move the pending items up behind it.
+            for k in (i..rest_end).rev() {
+                a.swap(k, k + block);
+            }
+
+            rest = i + block;
+            rest_end = next_end;
+        }
+    }
+
+    while rest < rest_end {
+        a.swap(out, rest);
+        out += 1;
+        rest += 1;
+    }
+}
+
+/// Merges pairs of runs without a buffer, by sorting their blocks with the
+/// tags `a[..tags]`, then merging neighbouring blocks by rotations.
+///
+/// The blocks are as short as the tags allow. A rotation merge of two blocks
+/// moves each item once for every place where the blocks interleave, which is
+/// at most once for every distinct value between them. The keys are all the
+/// distinct values there are, so each level moves every item a bounded number
+/// of times.
+fn lazy_level<T, F>(a: &mut [T], keys: usize, tags: usize, width: usize, compare: &mut F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let n = a.len();
+    let mut begin = keys;
+
+    // With fewer than two tags there is only one block: merge the whole runs.
+    let block = if tags >= 2 {
+        (2 * width).div_ceil(tags).next_power_of_two()
+    } else {
+        2 * width
+    };
+
+    while begin + width < n {
+        let middle = begin + width;
+        let end = (middle + width).min(n);
+
+        if end - middle <= block {
+            lazy_merge(&mut a[begin..end], width, compare);
+        } else {
+            let tail = middle + (end - middle) / block * block;
+            let left_blocks = width / block;
+            let blocks = (tail - begin) / block;
+
+            let first_right = sort_blocks(a, begin, block, blocks, left_blocks, compare);
+            lazy_merge_blocks(a, begin, block, blocks, first_right, compare);
+            heap_sort(&mut a[..blocks], compare);
+
+            if tail < end {
+                lazy_merge(&mut a[begin..end], tail - begin, compare);
+            }
+        }
+
+        begin = end;
+    }
+}
+
+/// Merges the sorted blocks from `a[begin]` into one run by rotations, like
+/// [`merge_blocks`] without the buffer.
+fn lazy_merge_blocks<T, F>(
+    a: &mut [T],
+    begin: usize,
+    block: usize,
+    blocks: usize,
+    first_right: usize,
+    compare: &mut F,
+) where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    // The pending items `a[rest..next]` all come from one run, and everything
+    // before them is final.
+    let mut rest = begin;
+    let mut rest_is_left = blocks > 0 && compare(&a[0], &a[first_right]) == Ordering::Less;
+
+    for i in 1..blocks {
+        let next = begin + i * block;
+        let next_is_left = compare(&a[i], &a[first_right]) == Ordering::Less;
+
+        if next_is_left == rest_is_left {
+            // Nothing after this can come before the pending items.
+            rest = next;
+        } else {
+            (rest, rest_is_left) =
+                lazy_merge_step(a, rest, next, next + block, next_is_left, compare);
+        }
+    }
+}
+
+/// Merges the pending items `a[rest..next]` with the block `a[next..end]` of
+/// the other run by rotations, until one of them runs out. Returns where the
+/// items still pending start, and whether they are of the left run.
+fn lazy_merge_step<T, F>(
+    a: &mut [T],
+    mut rest: usize,
+    mut next: usize,
+    end: usize,
+    next_is_left: bool,
+    compare: &mut F,
+) -> (usize, bool)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    // Whether an item of the block goes before an item of the pending run:
+    // when it is less, or equal and of the left run.
+    let mut before = |y: &T, x: &T| {
+        let order = compare(y, x);
+        order == Ordering::Less || (order == Ordering::Equal && next_is_left)
+    };
+
+    if !before(&a[next], &a[next - 1]) {
+        return (next, next_is_left);
+    }
+
+    loop {
+        // Rotate the pending items past the block's items that go before them.
+        let h = a[next..end].partition_point(|y| before(y, &a[rest]));
+        a[rest..next + h].rotate_left(next - rest);
+        rest += h;
+        next += h;
+
+        if next == end {
+            return (rest, !next_is_left);
+        }
+
+        // Pending items that go before the block's next item are final.
+        while rest < next && !before(&a[next], &a[rest]) {
+            rest += 1;
+        }
+
+        if rest == next {
+            return (next, next_is_left);
+        }
+    }
+}
+
+/// Merges the runs `a[..middle]` and `a[middle..]` by rotations, moving the
+/// shorter run along each time the runs interleave.
+fn lazy_merge<T, F>(a: &mut [T], middle: usize, compare: &mut F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let mut lo = 0;
+    let mut mid = middle;
+    let mut hi = a.len();
+
+    if lo == mid || mid == hi || compare(&a[mid], &a[mid - 1]) != Ordering::Less {
+        return;
+    }
+
+    if mid - lo <= hi - mid {
+        while lo < mid && mid < hi {
+            // Rotate the left run past the right items less than its first item.
+            let h = a[mid..hi].partition_point(|y| compare(y, &a[lo]) == Ordering::Less);
+            a[lo..mid + h].rotate_left(mid - lo);
+            lo += h;
+            mid += h;
+
+            // Left items not greater than the next right item are final.
+            while lo < mid && mid < hi && compare(&a[mid], &a[lo]) != Ordering::Less {
+                lo += 1;
+            }
+        }
+    } else {
+        while lo < mid && mid < hi {
+            // Rotate the right run back past the left items greater than its last item.
+            let h =
+                mid - lo - a[lo..mid].partition_point(|x| compare(&a[hi - 1], x) != Ordering::Less);
+            a[mid - h..hi].rotate_left(h);
+            mid -= h;
+            hi -= h;
+
+            // Right items not less than the previous left item are final.
+            while lo < mid && mid < hi && compare(&a[hi - 1], &a[mid - 1]) != Ordering::Less {
+                hi -= 1;
+            }
+        }
+    }
+}
+
+/// Merges the runs `a[begin..middle]` and `a[middle..end]` into
+/// `a[buffer..buffer + end - middle]`, swapping them with the buffer
+/// `a[buffer..begin]`, which ends up behind the merged run.
+///
+/// The buffer must be at least as long as the right run.
+fn merge_left<T, F>(
+    a: &mut [T],
+    buffer: usize,
+    begin: usize,
+    middle: usize,
+    end: usize,
+    compare: &mut F,
+) where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    debug_assert!(begin - buffer >= end - middle);
+
+    let mut out = buffer;
+    let mut i = begin;
+    let mut j = middle;
+
+    while i < middle && j < end {
+        if compare(&a[j], &a[i]) == Ordering::Less {
+            a.swap(out, j);
+            j += 1;
+        } else {
+            a.swap(out, i);
+            i += 1;
+        }
+
+        out += 1;
+    }
+
+    while i < middle {
+        a.swap(out, i);
+        out += 1;
+        i += 1;
+    }
+
+    while j < end {
+        a.swap(out, j);
+        out += 1;
+        j += 1;
+    }
+}
+
+/// Gathers the first occurrences of up to `wanted` distinct values, sorted, at
+/// the front of the array, keeping the rest in order. Returns their count.
+fn collect_keys<T, F>(a: &mut [T], wanted: usize, compare: &mut F) -> usize
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    // The keys are `a[first..first + keys]`, moved along behind the scan.
+    let mut first = 0;
+    let mut keys = 1;
+
+    for i in 1..a.len() {
+        if keys == wanted {
+            break;
+        }
+
+        let pos = a[first..first + keys].partition_point(|k| compare(k, &a[i]) == Ordering::Less);
+
+        if pos < keys && compare(&a[first + pos], &a[i]) == Ordering::Equal {
+            continue;
+        }
+
+        a[first..i].rotate_left(keys);
+        first = i - keys;
+        a[first + pos..=i].rotate_right(1);
+        keys += 1;
+    }
+
+    a[..first + keys].rotate_right(keys);
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::distributions::Uniform;
+    use rand::Rng;
+
+    use super::super::scratch;
+    use super::*;
+
+    fn check_orderly<T: Ord>(a: &[T]) {
+        if a.is_empty() {
+            return;
+        }
+
+        for i in 0..a.len() - 1 {
+            assert!(a[i] <= a[i + 1]);
+        }
+    }
+
+    /// Sorts `(key, index)` pairs by key and checks the result against std's stable sort.
+    fn check_stable(keys: Vec<i32>, buffer: usize) {
+        let mut data = keys
+            .into_iter()
+            .enumerate()
+            .map(|(i, x)| (x, i))
+            .collect::<Vec<(i32, usize)>>();
+
+        let mut expected = data.clone();
+        expected.sort_by_key(|x| x.0);
+
+        let mut b = scratch(buffer);
+        sort_in_place_by_with_buffer(&mut data, &mut b, |x, y| x.0.cmp(&y.0));
+        assert_eq!(data, expected);
+    }
+
+    #[test]
+    fn test_basic() {
+        let mut data = vec![5, 9, 3];
+        let sorted = vec![3, 5, 9];
+        sort_in_place(&mut data);
+        check_orderly(&data);
+        assert_eq!(data, sorted);
+    }
+
+    #[test]
+    fn test_batch() {
+        check_orderly(sort_in_place::<i32>(&mut []));
+        check_orderly(sort_in_place(&mut [5]));
+        check_orderly(sort_in_place(&mut [3, 2]));
+        check_orderly(sort_in_place(&mut [2, 3]));
+        check_orderly(sort_in_place(&mut [5, 1, 2]));
+        check_orderly(sort_in_place(&mut [1, 100, 2, 3]));
+        check_orderly(sort_in_place(&mut [1, 3, 5, 7, 9, 2, 4, 6, 8, 0]));
+        check_orderly(sort_in_place(&mut [2, 4, 6, 2, 1, 8, 10, 3, 5, 7, 0, 9, 1]));
+        check_orderly(sort_in_place(&mut [
+            9, 11, 9, 9, 9, 9, 11, 2, 3, 4, 11, 9, 0, 0, 0, 0,
+        ]));
+        check_orderly(sort_in_place(&mut [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10]));
+        check_orderly(sort_in_place(&mut [10, 9, 8, 7, 6, 5, 4, 3, 2, 1, 0]));
+        check_orderly(sort_in_place(&mut [
+            0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 0, 0, 0, 1, 2,
+        ]));
+        check_orderly(sort_in_place(&mut [
+            5, 4, 3, 2, 1, 5, 4, 3, 2, 1, 5, 4, 3, 2, 1,
+        ]));
+    }
+
+    #[test]
+    fn test_in_place_stable() {
+        let mut rng = rand::thread_rng();
+
+        // Few distinct values leave no room for the keys, many fill them all.
+        for (len, max) in [
+            (17, 3),
+            (100, 1000),
+            (1000, 1),
+            (1000, 20),
+            (1000, 100),
+            (4096, 100_000),
+            (5000, 60),
+            (20000, 500),
+            (30000, 1_000_000),
+        ] {
+            for buffer in [0, 8, 100] {
+                let keys = (&mut rng)
+                    .sample_iter(Uniform::new_inclusive(0, max))
+                    .take(len)
+                    .collect::<Vec<i32>>();
+                check_stable(keys, buffer);
+            }
+        }
+    }
+
+    #[test]
+    fn test_few_keys_comparisons() {
+        let mut rng = rand::thread_rng();
+        let size = 100_000;
+
+        // About 100 distinct values, far fewer than the 2√n keys a buffer needs.
+        for buffer in [0, 100] {
+            let keys = (&mut rng)
+                .sample_iter(Uniform::new(0, 100))
+                .take(size)
+                .collect::<Vec<i32>>();
+            let mut data = keys
+                .into_iter()
+                .enumerate()
+                .map(|(i, x)| (x, i))
+                .collect::<Vec<(i32, usize)>>();
+
+            let mut expected = data.clone();
+            expected.sort_by_key(|x| x.0);
+
+            let mut count = 0;
+            let mut b = scratch(buffer);
+            sort_in_place_by_with_buffer(&mut data, &mut b, |x, y| {
+                count += 1;
+                x.0.cmp(&y.0)
+            });
+            assert_eq!(data, expected);
+
+            let log = (usize::BITS - size.leading_zeros()) as usize;
+            assert!(count < 4 * size * log, "{} comparisons", count);
+        }
+    }
+
+    #[test]
+    fn test_in_place_patterns() {
+        let size = 10000;
+
+        for keys in [
+            (0..size).collect::<Vec<i32>>(),
+            (0..size).rev().collect::<Vec<i32>>(),
+            (0..size).map(|i| i % 7).collect::<Vec<i32>>(),
+            (0..size).map(|i| (size - i) / 100).collect::<Vec<i32>>(),
+            (0..size).map(|i| (i * 7919) % 1000).collect::<Vec<i32>>(),
+        ] {
+            check_stable(keys, 0);
+        }
+    }
+
+    #[test]
+    fn test_with_buffer() {
+        let mut rng = rand::thread_rng();
+        let range = Uniform::new_inclusive(-50000, 50000);
+        let data = (&mut rng)
+            .sample_iter(range)
+            .take(20000)
+            .collect::<Vec<i32>>();
+
+        let mut expected = data.clone();
+        expected.sort();
+
+        for buffer in [1, 16, 500, 10000] {
+            let mut b = scratch(buffer);
+            let mut sorted = data.clone();
+            sort_in_place_with_buffer(&mut sorted, &mut b);
+            assert_eq!(sorted, expected);
+        }
+    }
+
+    #[test]
+    fn test_strings() {
+        let mut data = (0..3000)
+            .map(|i| format!("{:x}", (i * 2_654_435_761u64) % 5000))
+            .collect::<Vec<String>>();
+        let mut expected = data.clone();
+        expected.sort();
+
+        sort_in_place(&mut data);
+        assert_eq!(data, expected);
+    }
+}
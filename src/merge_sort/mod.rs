@@ -4,76 +4,675 @@
 //!
 //! <https://en.wikipedia.org/wiki/Merge_sort>
 //!
+//! <https://en.wikipedia.org/wiki/K-way_merge_algorithm>
+//!
 //! Test case is derived from
 //!
 //! <https://github.com/rust-lang/rust/blob/60bd3f96779dbe6bd206dae09395e9af7d580552/library/alloc/src/collections/binary_heap/tests.rs>
 
+mod in_place;
+mod sorted_slices;
+
+use std::cmp::Ordering;
+use std::mem::{self, MaybeUninit};
+use std::ptr;
+use std::thread;
+
+use crate::binary_heap;
+use crate::insertion_sort::{binary_insertion_sort, SortOptions};
+
+pub use in_place::{
+    sort_in_place, sort_in_place_by, sort_in_place_by_with_buffer, sort_in_place_with_buffer,
+};
+pub use sorted_slices::{
+    difference, difference_into, intersection, intersection_into, merge_in_place,
+    merge_in_place_by, merge_into, merge_into_by, symmetric_difference, symmetric_difference_into,
+    union, union_into, Difference, Intersection, SymmetricDifference, Union,
+};
+
+/// Runs up to this length are sorted, or merged, on the current thread by [`par_sort`].
+const PAR_THRESHOLD: usize = 1 << 13;
+
 /// Sorting the entire array.
-pub fn sort<T: Ord + Copy>(a: &mut [T]) -> &mut [T] {
+pub fn sort<T: Ord>(a: &mut [T]) -> &mut [T] {
+    sort_by(a, T::cmp)
+}
+
+/// Sorting the entire array in descending order.
+pub fn sort_descending<T: Ord>(a: &mut [T]) -> &mut [T] {
+    sort_by(a, |x, y| y.cmp(x))
+}
+
+/// Sorting the entire array with a comparator function.
+///
+/// The sort is stable: equal elements keep their original order.
+pub fn sort_by<T, F>(a: &mut [T], compare: F) -> &mut [T]
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    sort_by_with_options(a, SortOptions::default(), compare)
+}
+
+/// Sorting the entire array, tuned by `options`.
+pub fn sort_with_options<T: Ord>(a: &mut [T], options: SortOptions) -> &mut [T] {
+    sort_by_with_options(a, options, T::cmp)
+}
+
+/// Sorting the entire array with a comparator function, tuned by `options`.
+///
+/// The sort is stable: equal elements keep their original order.
+pub fn sort_by_with_options<T, F>(a: &mut [T], options: SortOptions, mut compare: F) -> &mut [T]
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
     let size = a.len();
 
     if size <= 1 {
         return a;
     }
 
-    // One time copy of `a[]` to `b[]`.
-    let mut b = a.to_vec();
+    // Work array, large enough to hold the shorter run of any merge.
+    let mut b = scratch(size / 2);
 
-    merge_sort(a, &mut b, size);
+    merge_sort(a, &mut b, size, options.insertion_threshold, &mut compare);
     a
 }
 
+/// Sorting the entire array with a key extraction function.
+///
+/// The sort is stable: elements with equal keys keep their original order.
+pub fn sort_by_key<T, K, F>(a: &mut [T], mut f: F) -> &mut [T]
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    sort_by(a, |x, y| f(x).cmp(&f(y)))
+}
+
+/// Sorting the entire array with a key extraction function, in descending order.
+///
+/// The sort is stable: elements with equal keys keep their original order.
+pub fn sort_by_key_descending<T, K, F>(a: &mut [T], mut f: F) -> &mut [T]
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    sort_by(a, |x, y| f(y).cmp(&f(x)))
+}
+
+/// Sorting the entire array bottom-up, without recursion.
+pub fn sort_bottom_up<T: Ord>(a: &mut [T]) -> &mut [T] {
+    sort_bottom_up_by(a, T::cmp)
+}
+
+/// Sorting the entire array bottom-up with a comparator function, without recursion.
+///
+/// The sort is stable: equal elements keep their original order.
+pub fn sort_bottom_up_by<T, F>(a: &mut [T], mut compare: F) -> &mut [T]
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let size = a.len();
+
+    if size <= 1 {
+        return a;
+    }
+
+    // Work array, large enough to hold the shorter run of any merge.
+    let mut b = scratch(size / 2);
+
+    bottom_up_merge_sort(a, &mut b, size, &mut compare);
+    a
+}
+
+/// Sorting the entire array on multiple threads.
+///
+/// The result is exactly the one of [`sort`].
+pub fn par_sort<T: Ord + Send>(a: &mut [T]) -> &mut [T] {
+    par_sort_by(a, T::cmp)
+}
+
+/// Sorting the entire array on multiple threads with a comparator function.
+///
+/// The sort is stable: equal elements keep their original order.
+pub fn par_sort_by<T, F>(a: &mut [T], compare: F) -> &mut [T]
+where
+    T: Send,
+    F: Fn(&T, &T) -> Ordering + Sync,
+{
+    let size = a.len();
+
+    if size <= 1 {
+        return a;
+    }
+
+    // Work array, large enough to hold both runs of the final merge.
+    let mut b = scratch(size);
+
+    // Split into about twice as many tasks as there are threads.
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let depth = usize::BITS - threads.leading_zeros();
+
+    par_split_merge(a, &mut b, depth, &compare);
+    a
+}
+
+/// Lazily merges any number of sorted iterators into one sorted iterator.
+///
+/// The merge is stable: equal items come out of earlier iterators first.
+pub fn kmerge<I>(iters: I) -> impl Iterator<Item = <I::Item as IntoIterator>::Item>
+where
+    I: IntoIterator,
+    I::Item: IntoIterator,
+    <I::Item as IntoIterator>::Item: Ord,
+{
+    kmerge_by(iters, Ord::cmp)
+}
+
+/// Lazily merges any number of iterators, each sorted by `compare`, into one
+/// sorted iterator.
+///
+/// The merge is stable: equal items come out of earlier iterators first.
+pub fn kmerge_by<I, F>(
+    iters: I,
+    compare: F,
+) -> impl Iterator<Item = <I::Item as IntoIterator>::Item>
+where
+    I: IntoIterator,
+    I::Item: IntoIterator,
+    F: FnMut(&<I::Item as IntoIterator>::Item, &<I::Item as IntoIterator>::Item) -> Ordering,
+{
+    KMerge::new(
+        iters.into_iter().map(IntoIterator::into_iter).collect(),
+        compare,
+    )
+}
+
+/// Allocates an uninitialized work array of `len` items.
+pub(crate) fn scratch<T>(len: usize) -> Vec<MaybeUninit<T>> {
+    let mut b = Vec::with_capacity(len);
+    b.resize_with(len, MaybeUninit::uninit);
+    b
+}
+
 /// Array `a[]` has the items to sort; array `b[]` is a work array.
-fn merge_sort<T: Ord + Copy>(a: &mut [T], b: &mut [T], n: usize) {
-    // Sort data from `b[]` into `a[]`.
-    split_merge(b, 0, n, a);
+fn merge_sort<T, F>(
+    a: &mut [T],
+    b: &mut [MaybeUninit<T>],
+    n: usize,
+    threshold: usize,
+    compare: &mut F,
+) where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    split_merge(a, 0, n, b, threshold, compare);
+}
+
+/// Array `a[]` has the items to sort; array `b[]` is a work array.
+fn bottom_up_merge_sort<T, F>(a: &mut [T], b: &mut [MaybeUninit<T>], n: usize, compare: &mut F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    // Each 1-element run in `a[]` is already "sorted".
+    // Make successively longer sorted runs of length 2, 4, 8, 16... until the whole array is sorted.
+    let mut width = 1;
+
+    while width < n {
+        let mut begin = 0;
+
+        // Array `a[]` is full of runs of length `width`.
+        // Merge pairs of runs while there is a right run to merge with,
+        // a lone (shorter) run at the tail is left for a later pass.
+        while begin + width < n {
+            let middle = begin + width;
+            let end = (middle + width).min(n);
+
+            merge(a, begin, middle, end, b, compare);
+            begin = end;
+        }
+
+        // Now array `a[]` is full of runs of length `2 * width`.
+        width *= 2;
+    }
 }
 
-/// Split `a[]` into 2 runs, sort both runs into `b[]`, merge both runs from `b[]` to `a[]`.
+/// Split `a[]` into 2 runs, sort both runs in place, merge both runs back into `a[]`
+/// using `b[]` as a work array.
+///
+/// Runs up to `threshold` long are binary insertion sorted instead.
 ///
 /// [begin, end);
-fn split_merge<T: Ord + Copy>(b: &mut [T], begin: usize, end: usize, a: &mut [T]) {
+fn split_merge<T, F>(
+    a: &mut [T],
+    begin: usize,
+    end: usize,
+    b: &mut [MaybeUninit<T>],
+    threshold: usize,
+    compare: &mut F,
+) where
+    F: FnMut(&T, &T) -> Ordering,
+{
     // If run size == 1, consider it sorted.
     if end - begin <= 1 {
         return;
     }
 
+    // Short runs are cheaper to insertion sort, which is stable too.
+    if end - begin <= threshold {
+        binary_insertion_sort(&mut a[begin..end], 1, compare);
+        return;
+    }
+
     // Split the run longer than 1 item into halves.
     // middle = mid point;
     let middle = begin + (end - begin) / 2;
 
-    // Recursively sort both runs from array `a[]` into `b[]`.
+    // Recursively sort both runs.
     // Sort the left run.
-    split_merge(a, begin, middle, b);
+    split_merge(a, begin, middle, b, threshold, compare);
     // Sort the right run.
-    split_merge(a, middle, end, b);
+    split_merge(a, middle, end, b, threshold, compare);
 
-    // Merge the resulting runs from array `b[]` into `a[]`.
-    merge(b, begin, middle, end, a);
+    // Merge the resulting runs back into `a[]`.
+    merge(a, begin, middle, end, b, compare);
 }
 
 /// Left source half is `a[begin, middle - 1]`.
 ///
 /// Right source half is `a[middle, end - 1]`.
 ///
-/// Result is `b[begin, end - 1]`.
-fn merge<T: Ord + Copy>(a: &mut [T], begin: usize, middle: usize, end: usize, b: &mut [T]) {
-    let mut i = begin;
-    let mut j = middle;
+/// Result is `a[begin, end - 1]`.
+///
+/// The shorter half is moved out into `b[]` first, so `b[]` must hold at least
+/// `(end - begin) / 2` items. Elements are moved, never copied: if `compare`
+/// panics, the items still in `b[]` are moved back into the gap left in `a[]`.
+pub(crate) fn merge<T, F>(
+    a: &mut [T],
+    begin: usize,
+    middle: usize,
+    end: usize,
+    b: &mut [MaybeUninit<T>],
+    compare: &mut F,
+) where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let len = end - begin;
+    let mid = middle - begin;
+
+    if mid == 0 || mid == len {
+        return;
+    }
+
+    assert!(b.len() >= mid.min(len - mid));
+
+    let v = a[begin..end].as_mut_ptr();
+    let buf = b.as_mut_ptr() as *mut T;
+
+    // SAFETY: `v` points to `len` initialized items and `buf` has room for the
+    // shorter run. Every item is always owned by exactly one of `a[]` or `b[]`:
+    // `hole` tracks the items still in `b[]` and where they belong in `a[]`.
+    unsafe {
+        if mid <= len - mid {
+            // The left run is shorter, so merge forwards.
+            ptr::copy_nonoverlapping(v, buf, mid);
+            let mut hole = MergeHole {
+                start: buf,
+                end: buf.add(mid),
+                dest: v,
+            };
+
+            let mut right = v.add(mid);
+            let right_end = v.add(len);
+
+            // [dest, right) is the gap, and it is exactly as long as [start, end).
+            while hole.start < hole.end && right < right_end {
+                // Take from the right run only if it is strictly smaller.
+                let to_move = if compare(&*right, &*hole.start) == Ordering::Less {
+                    let item = right;
+                    right = right.add(1);
+                    item
+                } else {
+                    let item = hole.start;
+                    hole.start = hole.start.add(1);
+                    item
+                };
+
+                ptr::copy_nonoverlapping(to_move, hole.dest, 1);
+                hole.dest = hole.dest.add(1);
+            }
+        } else {
+            // The right run is shorter, so merge backwards.
+            ptr::copy_nonoverlapping(v.add(mid), buf, len - mid);
+            let mut hole = MergeHole {
+                start: buf,
+                end: buf.add(len - mid),
+                dest: v.add(mid),
+            };
+
+            let mut out = v.add(len);
+
+            // [dest, out) is the gap, and it is exactly as long as [start, end).
+            while v < hole.dest && hole.start < hole.end {
+                out = out.sub(1);
+
+                let left = hole.dest.sub(1);
+                let right = hole.end.sub(1);
+
+                // Take from the left run only if it is strictly greater.
+                let to_move = if compare(&*right, &*left) == Ordering::Less {
+                    hole.dest = left;
+                    left
+                } else {
+                    hole.end = right;
+                    right
+                };
+
+                ptr::copy_nonoverlapping(to_move, out, 1);
+            }
+        }
+        // Whatever is left in `b[]` is moved into the gap when `hole` is dropped.
+    }
+}
+
+/// Items `[start, end)` of the work array that still have to be moved to `dest`.
+pub(crate) struct MergeHole<T> {
+    pub(crate) start: *mut T,
+    pub(crate) end: *mut T,
+    pub(crate) dest: *mut T,
+}
+
+impl<T> Drop for MergeHole<T> {
+    fn drop(&mut self) {
+        // SAFETY: `[start, end)` are initialized items of the work array, and
+        // the gap at `dest` in the sorted array is exactly as long.
+        unsafe {
+            let len = self.end.offset_from(self.start) as usize;
+            ptr::copy_nonoverlapping(self.start, self.dest, len);
+        }
+    }
+}
+
+/// Like [`split_merge`] over the whole of `a[]`, but sorts the two runs on two
+/// threads, and merges them with [`par_merge`].
+///
+/// `b[]` must be as long as `a[]`.
+fn par_split_merge<T, F>(a: &mut [T], b: &mut [MaybeUninit<T>], depth: u32, compare: &F)
+where
+    T: Send,
+    F: Fn(&T, &T) -> Ordering + Sync,
+{
+    let n = a.len();
+
+    if depth == 0 || n <= PAR_THRESHOLD {
+        let threshold = SortOptions::default().insertion_threshold;
+        split_merge(a, 0, n, b, threshold, &mut |x: &T, y: &T| compare(x, y));
+        return;
+    }
+
+    let middle = n / 2;
+
+    {
+        let (left, right) = a.split_at_mut(middle);
+        let (left_b, right_b) = b.split_at_mut(middle);
+
+        thread::scope(|s| {
+            s.spawn(|| par_split_merge(left, left_b, depth - 1, compare));
+            par_split_merge(right, right_b, depth - 1, compare);
+        });
+    }
+
+    par_merge(a, middle, b, depth, compare);
+}
+
+/// Merges the sorted runs `a[0, middle - 1]` and `a[middle, len - 1]` on multiple threads.
+///
+/// Both runs are moved out into `b[]`, then merged back into `a[]` in pieces
+/// that are independent of each other. If `compare` panics, `a[]` is restored
+/// from `b[]`.
+fn par_merge<T, F>(a: &mut [T], middle: usize, b: &mut [MaybeUninit<T>], depth: u32, compare: &F)
+where
+    T: Send,
+    F: Fn(&T, &T) -> Ordering + Sync,
+{
+    let len = a.len();
+    let v = a.as_mut_ptr();
+    let buf = b.as_mut_ptr() as *mut T;
+
+    // SAFETY: `b[]` has room for all `len` items. While the pieces are merged,
+    // `b[]` owns every item and `a[]` only holds bitwise copies, which `restore`
+    // writes over if a comparison panics.
+    unsafe {
+        ptr::copy_nonoverlapping(v, buf, len);
+
+        let restore = CopyOnDrop {
+            src: buf,
+            dest: v,
+            len,
+        };
+
+        par_merge_into(
+            Run::new(buf, middle),
+            Run::new(buf.add(middle), len - middle),
+            SendPtr(v),
+            depth,
+            compare,
+        );
+
+        mem::forget(restore);
+    }
+}
+
+/// Merges the sorted runs `left[]` and `right[]` into `dest[]`, splitting the
+/// work in two independent pieces while `depth` allows.
+///
+/// The longer run is split at its midpoint, and the other one where that
+/// midpoint item would be inserted, so that items from `left[]` still come
+/// before equal items from `right[]`.
+///
+/// # Safety
+///
+/// `dest[]` must have room for `left.len + right.len` items, and must not
+/// overlap either run.
+unsafe fn par_merge_into<T, F>(
+    left: Run<T>,
+    right: Run<T>,
+    dest: SendPtr<T>,
+    depth: u32,
+    compare: &F,
+) where
+    T: Send,
+    F: Fn(&T, &T) -> Ordering + Sync,
+{
+    if depth == 0 || left.len + right.len <= PAR_THRESHOLD {
+        merge_runs_into(left, right, dest.0, compare);
+        return;
+    }
+
+    let (left_mid, right_mid) = if left.len >= right.len {
+        let left_mid = left.len / 2;
+        let key = &*left.ptr.add(left_mid);
+        let right_mid = right
+            .as_slice()
+            .partition_point(|x| compare(x, key) == Ordering::Less);
+        (left_mid, right_mid)
+    } else {
+        let right_mid = right.len / 2;
+        let key = &*right.ptr.add(right_mid);
+        let left_mid = left
+            .as_slice()
+            .partition_point(|x| compare(x, key) != Ordering::Greater);
+        (left_mid, right_mid)
+    };
+
+    let (left_lo, left_hi) = left.split_at(left_mid);
+    let (right_lo, right_hi) = right.split_at(right_mid);
+    let dest_hi = SendPtr(dest.0.add(left_mid + right_mid));
+
+    thread::scope(|s| {
+        s.spawn(|| par_merge_into(left_lo, right_lo, dest, depth - 1, compare));
+        par_merge_into(left_hi, right_hi, dest_hi, depth - 1, compare);
+    });
+}
+
+/// Merges the sorted runs `left[]` and `right[]` into `dest[]` on the current thread.
+///
+/// # Safety
+///
+/// Same as [`par_merge_into`].
+unsafe fn merge_runs_into<T, F>(left: Run<T>, right: Run<T>, dest: *mut T, compare: &F)
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    let mut i = 0;
+    let mut j = 0;
 
     // While there are elements in the left or right runs...
-    for k in begin..end {
+    for k in 0..left.len + right.len {
         // If left run head exists and is <= existing right run head.
-        if i < middle && (j >= end || a[i] <= a[j]) {
-            b[k] = a[i];
+        let item = if i < left.len
+            && (j >= right.len
+                || compare(&*left.ptr.add(i), &*right.ptr.add(j)) != Ordering::Greater)
+        {
             i += 1;
+            left.ptr.add(i - 1)
         } else {
-            b[k] = a[j];
             j += 1;
+            right.ptr.add(j - 1)
+        };
+
+        ptr::copy_nonoverlapping(item, dest.add(k), 1);
+    }
+}
+
+/// A sorted run of `len` items in the work array of [`par_merge`].
+struct Run<T> {
+    ptr: *const T,
+    len: usize,
+}
+
+impl<T> Run<T> {
+    fn new(ptr: *const T, len: usize) -> Self {
+        Run { ptr, len }
+    }
+
+    /// # Safety
+    ///
+    /// The run must point to `len` initialized items.
+    unsafe fn as_slice(&self) -> &[T] {
+        std::slice::from_raw_parts(self.ptr, self.len)
+    }
+
+    fn split_at(self, mid: usize) -> (Run<T>, Run<T>) {
+        // SAFETY: `mid <= len`, so both halves stay within the run.
+        let hi = unsafe { self.ptr.add(mid) };
+        (Run::new(self.ptr, mid), Run::new(hi, self.len - mid))
+    }
+}
+
+/// A destination pointer that can be handed to another thread.
+struct SendPtr<T>(*mut T);
+
+impl<T> Clone for SendPtr<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for SendPtr<T> {}
+
+// SAFETY: every thread is handed disjoint parts of the work array and of the
+// destination, so this is like sending `&mut [T]` around, which needs `T: Send`.
+unsafe impl<T: Send> Send for Run<T> {}
+unsafe impl<T: Send> Send for SendPtr<T> {}
+unsafe impl<T: Send> Sync for SendPtr<T> {}
+
+/// Copies `len` items from `src` back to `dest` when dropped.
+struct CopyOnDrop<T> {
+    src: *const T,
+    dest: *mut T,
+    len: usize,
+}
+
+impl<T> Drop for CopyOnDrop<T> {
+    fn drop(&mut self) {
+        // SAFETY: only created by `par_merge`, over its whole range.
+        unsafe {
+            ptr::copy_nonoverlapping(self.src, self.dest, self.len);
         }
     }
 }
 
+/// The k-way merge of [`kmerge_by`]: a min-heap of the next item of every
+/// source, tagged with the index of the source to break ties.
+struct KMerge<I: Iterator, F> {
+    heap: Vec<(I::Item, usize)>,
+    sources: Vec<I>,
+    compare: F,
+}
+
+impl<I, F> KMerge<I, F>
+where
+    I: Iterator,
+    F: FnMut(&I::Item, &I::Item) -> Ordering,
+{
+    fn new(mut sources: Vec<I>, compare: F) -> Self {
+        let heap = sources
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(i, source)| source.next().map(|item| (item, i)))
+            .collect();
+
+        let mut merge = KMerge {
+            heap,
+            sources,
+            compare,
+        };
+        binary_heap::heapify_by(&mut merge.heap, &mut by_source(&mut merge.compare));
+        merge
+    }
+}
+
+/// Orders the heap entries of [`KMerge`] by item, then by the index of their source.
+fn by_source<T, F>(compare: &mut F) -> impl FnMut(&(T, usize), &(T, usize)) -> Ordering + '_
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    |x, y| compare(&x.0, &y.0).then(x.1.cmp(&y.1))
+}
+
+impl<I, F> Iterator for KMerge<I, F>
+where
+    I: Iterator,
+    F: FnMut(&I::Item, &I::Item) -> Ordering,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let source = self.heap.first()?.1;
+
+        // Replace the least item with the next one of its source, if there is one.
+        let item = match self.sources[source].next() {
+            Some(next) => mem::replace(&mut self.heap[0], (next, source)).0,
+            None => self.heap.swap_remove(0).0,
+        };
+
+        binary_heap::sift_down_by(&mut self.heap, 0, &mut by_source(&mut self.compare));
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.sources.iter().map(Iterator::size_hint).fold(
+            (self.heap.len(), Some(self.heap.len())),
+            |(lo, hi), x| {
+                (
+                    lo.saturating_add(x.0),
+                    hi.zip(x.1).and_then(|(a, b)| a.checked_add(b)),
+                )
+            },
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use rand::distributions::Uniform;
@@ -175,4 +774,366 @@ mod tests {
         sort(&mut data);
         check_orderly(&data);
     }
+
+    #[test]
+    fn test_descending() {
+        let mut data = vec![2, 4, 6, 2, 1, 8, 10, 3, 5, 7, 0, 9, 1];
+        let sorted = vec![10, 9, 8, 7, 6, 5, 4, 3, 2, 2, 1, 1, 0];
+        sort_descending(&mut data);
+        assert_eq!(data, sorted);
+    }
+
+    #[test]
+    fn test_sort_by() {
+        let mut data = vec![(1, 'b'), (3, 'a'), (2, 'c'), (0, 'a')];
+        sort_by(&mut data, |x, y| x.1.cmp(&y.1));
+        assert_eq!(data, vec![(3, 'a'), (0, 'a'), (1, 'b'), (2, 'c')]);
+    }
+
+    #[test]
+    fn test_sort_by_key_stable() {
+        let mut data = vec![-10, 1, 2, 3, -3, -20, 5, 43, -1, 10];
+        sort_by_key(&mut data, |x: &i32| x.abs());
+        assert_eq!(data, vec![1, -1, 2, 3, -3, 5, -10, 10, -20, 43]);
+
+        sort_by_key_descending(&mut data, |x: &i32| x.abs());
+        assert_eq!(data, vec![43, -20, -10, 10, 5, 3, -3, 2, 1, -1]);
+    }
+
+    #[test]
+    fn test_bottom_up_batch() {
+        check_orderly(sort_bottom_up::<i32>(&mut []));
+        check_orderly(sort_bottom_up(&mut [5]));
+        check_orderly(sort_bottom_up(&mut [3, 2]));
+        check_orderly(sort_bottom_up(&mut [2, 3]));
+        check_orderly(sort_bottom_up(&mut [5, 1, 2]));
+        check_orderly(sort_bottom_up(&mut [1, 100, 2, 3]));
+        check_orderly(sort_bottom_up(&mut [1, 3, 5, 7, 9, 2, 4, 6, 8, 0]));
+        check_orderly(sort_bottom_up(&mut [
+            2, 4, 6, 2, 1, 8, 10, 3, 5, 7, 0, 9, 1,
+        ]));
+        check_orderly(sort_bottom_up(&mut [
+            9, 11, 9, 9, 9, 9, 11, 2, 3, 4, 11, 9, 0, 0, 0, 0,
+        ]));
+        check_orderly(sort_bottom_up(&mut [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10]));
+        check_orderly(sort_bottom_up(&mut [10, 9, 8, 7, 6, 5, 4, 3, 2, 1, 0]));
+        check_orderly(sort_bottom_up(&mut [
+            0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 0, 0, 0, 1, 2,
+        ]));
+        check_orderly(sort_bottom_up(&mut [
+            5, 4, 3, 2, 1, 5, 4, 3, 2, 1, 5, 4, 3, 2, 1,
+        ]));
+    }
+
+    #[test]
+    fn test_bottom_up_matches_top_down() {
+        let mut rng = rand::thread_rng();
+        let range = Uniform::new_inclusive(0, 20);
+
+        // Every length up to a few powers of two, so every shape of tail is covered.
+        for size in 0..=70 {
+            let data = (&mut rng)
+                .sample_iter(range)
+                .take(size)
+                .enumerate()
+                .map(|(i, x)| (x, i))
+                .collect::<Vec<(i32, usize)>>();
+
+            let mut top_down = data.clone();
+            sort_by_key(&mut top_down, |x| x.0);
+
+            let mut bottom_up = data;
+            sort_bottom_up_by(&mut bottom_up, |x, y| x.0.cmp(&y.0));
+
+            assert_eq!(top_down, bottom_up);
+        }
+    }
+
+    #[test]
+    fn test_bottom_up_random_large() {
+        let mut rng = rand::thread_rng();
+        let range = Uniform::new_inclusive(-50000, 50000);
+        let mut data = (&mut rng)
+            .sample_iter(range)
+            .take(50001)
+            .collect::<Vec<i32>>();
+        sort_bottom_up(&mut data);
+        check_orderly(&data);
+    }
+
+    #[test]
+    fn test_par_batch() {
+        check_orderly(par_sort::<i32>(&mut []));
+        check_orderly(par_sort(&mut [5]));
+        check_orderly(par_sort(&mut [3, 2]));
+        check_orderly(par_sort(&mut [2, 3]));
+        check_orderly(par_sort(&mut [5, 1, 2]));
+        check_orderly(par_sort(&mut [1, 100, 2, 3]));
+        check_orderly(par_sort(&mut [1, 3, 5, 7, 9, 2, 4, 6, 8, 0]));
+        check_orderly(par_sort(&mut [2, 4, 6, 2, 1, 8, 10, 3, 5, 7, 0, 9, 1]));
+        check_orderly(par_sort(&mut [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 0, 0, 0, 1, 2]));
+        check_orderly(par_sort(&mut [5, 4, 3, 2, 1, 5, 4, 3, 2, 1, 5, 4, 3, 2, 1]));
+    }
+
+    #[test]
+    fn test_par_matches_sequential() {
+        let mut rng = rand::thread_rng();
+
+        for max in [10, 100000] {
+            let range = Uniform::new_inclusive(0, max);
+            let data = (&mut rng)
+                .sample_iter(range)
+                .take(200000)
+                .enumerate()
+                .map(|(i, x)| (x, i))
+                .collect::<Vec<(i32, usize)>>();
+
+            let mut sequential = data.clone();
+            sort_by_key(&mut sequential, |x| x.0);
+
+            let mut parallel = data;
+            par_sort_by(&mut parallel, |x, y| x.0.cmp(&y.0));
+
+            assert_eq!(sequential, parallel);
+        }
+    }
+
+    #[test]
+    fn test_par_strings() {
+        let mut rng = rand::thread_rng();
+        let range = Uniform::new_inclusive(0, 1000000);
+        let mut data = (&mut rng)
+            .sample_iter(range)
+            .take(50000)
+            .map(|x: i32| x.to_string())
+            .collect::<Vec<String>>();
+        par_sort(&mut data);
+        check_orderly(&data);
+    }
+
+    #[test]
+    fn test_par_panic_safety() {
+        use std::panic::{self, AssertUnwindSafe};
+        use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+
+        let mut rng = rand::thread_rng();
+        let range = Uniform::new_inclusive(0, 1000000);
+        let data = (&mut rng)
+            .sample_iter(range)
+            .take(50000)
+            .map(|x: i32| x.to_string())
+            .collect::<Vec<String>>();
+
+        for limit in [1000, 200000, 600000] {
+            let mut shuffled = data.clone();
+            let count = AtomicUsize::new(0);
+
+            let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                par_sort_by(&mut shuffled, |x, y| {
+                    if count.fetch_add(1, AtomicOrdering::Relaxed) == limit {
+                        panic!("comparison limit reached");
+                    }
+                    x.cmp(y)
+                });
+            }));
+            assert!(result.is_err());
+
+            // No element was lost or duplicated.
+            let mut expected = data.clone();
+            expected.sort();
+            shuffled.sort();
+            assert_eq!(expected, shuffled);
+        }
+    }
+
+    #[test]
+    fn test_sort_with_options() {
+        let mut rng = rand::thread_rng();
+        let range = Uniform::new_inclusive(0, 100);
+        let data = (&mut rng)
+            .sample_iter(range)
+            .take(5000)
+            .enumerate()
+            .map(|(i, x)| (x, i))
+            .collect::<Vec<(i32, usize)>>();
+
+        for insertion_threshold in [0, 1, 2, 5, 16, 100, 10000] {
+            let options = SortOptions {
+                insertion_threshold,
+            };
+
+            let mut sorted = data.clone();
+            sort_with_options(&mut sorted, options);
+            check_orderly(&sorted);
+
+            // Still stable with the cutoff.
+            let mut stable = data.clone();
+            sort_by_with_options(&mut stable, options, |x, y| x.0.cmp(&y.0));
+            check_orderly(&stable);
+        }
+    }
+
+    #[test]
+    fn test_strings() {
+        let mut data = ["pear", "apple", "fig", "banana", "cherry", "date", "apple"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<String>>();
+        sort(&mut data);
+        check_orderly(&data);
+        assert_eq!(
+            data,
+            ["apple", "apple", "banana", "cherry", "date", "fig", "pear"]
+        );
+
+        sort_by_key(&mut data, |s| s.len());
+        assert_eq!(
+            data,
+            ["fig", "date", "pear", "apple", "apple", "banana", "cherry"]
+        );
+    }
+
+    #[test]
+    fn test_boxed_stable() {
+        let mut rng = rand::thread_rng();
+        let range = Uniform::new_inclusive(0, 50);
+        let mut data = (&mut rng)
+            .sample_iter(range)
+            .take(1000)
+            .enumerate()
+            .map(|(i, x)| Box::new((x, i)))
+            .collect::<Vec<Box<(i32, usize)>>>();
+        sort_by_key(&mut data, |x| x.0);
+        check_orderly(&data);
+    }
+
+    #[test]
+    fn test_panic_safety() {
+        use std::cell::Cell;
+        use std::panic::{self, AssertUnwindSafe};
+        use std::rc::Rc;
+
+        struct Counted {
+            id: usize,
+            drops: Rc<Cell<usize>>,
+        }
+
+        impl Drop for Counted {
+            fn drop(&mut self) {
+                self.drops.set(self.drops.get() + 1);
+            }
+        }
+
+        let size = 200;
+        let mut rng = rand::thread_rng();
+
+        for limit in [0, 1, 10, 100, 500, 1000] {
+            let drops = Rc::new(Cell::new(0));
+            let mut data = (0..size)
+                .map(|_| Counted {
+                    id: rng.gen_range(0..size),
+                    drops: drops.clone(),
+                })
+                .collect::<Vec<Counted>>();
+            let mut ids = data.iter().map(|x| x.id).collect::<Vec<usize>>();
+
+            let mut count = 0;
+            let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                sort_by(&mut data, |x, y| {
+                    count += 1;
+                    if count > limit {
+                        panic!("comparison limit reached");
+                    }
+                    x.id.cmp(&y.id)
+                });
+            }));
+            assert!(result.is_err());
+
+            // No element was lost or duplicated.
+            let mut after = data.iter().map(|x| x.id).collect::<Vec<usize>>();
+            ids.sort();
+            after.sort();
+            assert_eq!(ids, after);
+            assert_eq!(drops.get(), 0);
+
+            drop(data);
+            assert_eq!(drops.get(), size);
+        }
+    }
+
+    #[test]
+    fn test_random_stable() {
+        let mut rng = rand::thread_rng();
+        let range = Uniform::new_inclusive(0, 100);
+        let mut data = (&mut rng)
+            .sample_iter(range)
+            .take(50000)
+            .enumerate()
+            .map(|(i, x)| (x, i))
+            .collect::<Vec<(i32, usize)>>();
+        sort_by_key(&mut data, |x| x.0);
+        check_orderly(&data);
+    }
+
+    #[test]
+    fn test_kmerge() {
+        let merged = kmerge(vec![vec![1, 4, 7], vec![], vec![2, 5, 8], vec![0, 3, 6, 9]]);
+        assert_eq!(merged.collect::<Vec<i32>>(), (0..10).collect::<Vec<i32>>());
+
+        assert_eq!(kmerge(Vec::<Vec<i32>>::new()).next(), None);
+        assert_eq!(kmerge([[5]]).collect::<Vec<i32>>(), vec![5]);
+
+        // Sources are only read as far as needed.
+        let evens = (0..).step_by(2);
+        let odds = (1..).step_by(2);
+        let merged = kmerge([evens, odds]).take(6).collect::<Vec<i32>>();
+        assert_eq!(merged, vec![0, 1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn test_kmerge_by_stable() {
+        let shards = vec![
+            vec![(3, 'a'), (2, 'a'), (2, 'b'), (0, 'a')],
+            vec![(3, 'b'), (2, 'c'), (1, 'a')],
+            vec![(2, 'd'), (0, 'b')],
+        ];
+        let merged = kmerge_by(shards, |x: &(i32, char), y| y.0.cmp(&x.0));
+        assert_eq!(
+            merged.collect::<Vec<(i32, char)>>(),
+            vec![
+                (3, 'a'),
+                (3, 'b'),
+                (2, 'a'),
+                (2, 'b'),
+                (2, 'c'),
+                (2, 'd'),
+                (1, 'a'),
+                (0, 'a'),
+                (0, 'b'),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_kmerge_random() {
+        let mut rng = rand::thread_rng();
+        let range = Uniform::new_inclusive(0, 1000);
+        let shards = (0..20)
+            .map(|shard| {
+                let mut data = (&mut rng)
+                    .sample_iter(range)
+                    .take(shard * 100)
+                    .collect::<Vec<i32>>();
+                data.sort();
+                data
+            })
+            .collect::<Vec<Vec<i32>>>();
+
+        let mut expected = shards.concat();
+        expected.sort();
+
+        let merged = kmerge(shards.iter().cloned());
+        assert_eq!(merged.size_hint(), (expected.len(), Some(expected.len())));
+        assert_eq!(merged.collect::<Vec<i32>>(), expected);
+    }
 }
@@ -0,0 +1,424 @@
+//! Merges and set operations on already sorted slices.
+//!
+//! All of them are stable: of two equal elements, the one from `left` comes first.
+//! The set operations treat their inputs as multisets, like
+//! <https://en.cppreference.com/w/cpp/algorithm/set_union>.
+
+use std::cmp::Ordering;
+use std::iter::FusedIterator;
+
+/// Merges the sorted `left[]` and `right[]` into `out[]`.
+///
+/// # Panics
+///
+/// Panics if `out` is not exactly as long as both inputs.
+pub fn merge_into<T: Ord + Clone>(left: &[T], right: &[T], out: &mut [T]) {
+    merge_into_by(left, right, out, T::cmp)
+}
+
+/// Merges `left[]` and `right[]`, both sorted by `compare`, into `out[]`.
+///
+/// # Panics
+///
+/// Panics if `out` is not exactly as long as both inputs.
+pub fn merge_into_by<T, F>(left: &[T], right: &[T], out: &mut [T], mut compare: F)
+where
+    T: Clone,
+    F: FnMut(&T, &T) -> Ordering,
+{
+    assert_eq!(
+        out.len(),
+        left.len() + right.len(),
+        "output length must be the sum of the input lengths"
+    );
+
+    let mut i = 0;
+    let mut j = 0;
+
+    for slot in out.iter_mut() {
+        // Take from the right only if it is strictly less, to keep the merge stable.
+        if j < right.len() && (i == left.len() || compare(&right[j], &left[i]) == Ordering::Less) {
+            *slot = right[j].clone();
+            j += 1;
+        } else {
+            *slot = left[i].clone();
+            i += 1;
+        }
+    }
+}
+
+/// Merges the sorted runs `a[..middle]` and `a[middle..]` in place, without a
+/// work array, in `O(n log n)` time.
+pub fn merge_in_place<T: Ord>(a: &mut [T], middle: usize) -> &mut [T] {
+    merge_in_place_by(a, middle, T::cmp)
+}
+
+/// Merges the runs `a[..middle]` and `a[middle..]`, both sorted by `compare`,
+/// in place, without a work array, in `O(n log n)` time.
+///
+/// # Panics
+///
+/// Panics if `middle > a.len()`.
+pub fn merge_in_place_by<T, F>(a: &mut [T], middle: usize, mut compare: F) -> &mut [T]
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    assert!(middle <= a.len(), "middle is out of bounds");
+    rotation_merge(a, middle, &mut compare);
+    a
+}
+
+/// Splits the longer run at its middle element, finds where that element goes
+/// in the other run, rotates the two inner parts past each other, then merges
+/// each side the same way.
+///
+/// The longer run halves at every level, so the recursion is `O(log n)` deep.
+pub(crate) fn rotation_merge<T, F>(a: &mut [T], middle: usize, compare: &mut F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let left_len = middle;
+    let right_len = a.len() - middle;
+
+    if left_len == 0 || right_len == 0 {
+        return;
+    }
+
+    if left_len + right_len == 2 {
+        if compare(&a[1], &a[0]) == Ordering::Less {
+            a.swap(0, 1);
+        }
+        return;
+    }
+
+    // Already in order, the common case for nearly sorted input.
+    if compare(&a[middle], &a[middle - 1]) != Ordering::Less {
+        return;
+    }
+
+    let (left_cut, right_cut) = if left_len >= right_len {
+        // Right items strictly less than the pivot go before it.
+        let left_cut = left_len / 2;
+        let pivot = &a[left_cut];
+        let offset = a[middle..].partition_point(|x| compare(x, pivot) == Ordering::Less);
+        (left_cut, middle + offset)
+    } else {
+        // Left items not greater than the pivot stay before it.
+        let right_cut = middle + right_len / 2;
+        let pivot = &a[right_cut];
+        let offset = a[..middle].partition_point(|x| compare(pivot, x) != Ordering::Less);
+        (offset, right_cut)
+    };
+
+    a[left_cut..right_cut].rotate_left(middle - left_cut);
+    let new_middle = left_cut + (right_cut - middle);
+
+    let (front, back) = a.split_at_mut(new_middle);
+    rotation_merge(front, left_cut, compare);
+    rotation_merge(back, right_cut - new_middle, compare);
+}
+
+/// One step of walking two sorted slices side by side.
+enum Step<'a, T> {
+    Left(&'a T),
+    Right(&'a T),
+    Both(&'a T),
+}
+
+/// Walks two sorted slices side by side, pairing up equal elements.
+#[derive(Debug)]
+struct Steps<'a, T> {
+    left: &'a [T],
+    right: &'a [T],
+}
+
+impl<'a, T: Ord> Iterator for Steps<'a, T> {
+    type Item = Step<'a, T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let step = match (self.left.first(), self.right.first()) {
+            (None, None) => return None,
+            (Some(x), None) => Step::Left(x),
+            (None, Some(y)) => Step::Right(y),
+            (Some(x), Some(y)) => match x.cmp(y) {
+                Ordering::Less => Step::Left(x),
+                Ordering::Greater => Step::Right(y),
+                Ordering::Equal => Step::Both(x),
+            },
+        };
+
+        match step {
+            Step::Left(_) => self.left = &self.left[1..],
+            Step::Right(_) => self.right = &self.right[1..],
+            Step::Both(_) => {
+                self.left = &self.left[1..];
+                self.right = &self.right[1..];
+            }
+        }
+
+        Some(step)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (l, r) = (self.left.len(), self.right.len());
+        (l.max(r), Some(l + r))
+    }
+}
+
+macro_rules! set_operation {
+    ($(#[$doc:meta])* $name:ident, $iter:ident, $into:ident, |$step:ident| $keep:expr) => {
+        $(#[$doc])*
+        pub fn $name<'a, T: Ord>(left: &'a [T], right: &'a [T]) -> $iter<'a, T> {
+            $iter {
+                steps: Steps { left, right },
+            }
+        }
+
+        #[doc = concat!("Writes [`", stringify!($name), "`] of the sorted `left[]` and `right[]` ")]
+        #[doc = "to the front of `out[]`, and returns the number of elements written."]
+        ///
+        /// # Panics
+        ///
+        /// Panics if `out` is too short.
+        pub fn $into<T: Ord + Clone>(left: &[T], right: &[T], out: &mut [T]) -> usize {
+            let mut n = 0;
+
+            for x in $name(left, right) {
+                out[n] = x.clone();
+                n += 1;
+            }
+
+            n
+        }
+
+        #[doc = concat!("The lazy iterator returned by [`", stringify!($name), "`].")]
+        #[derive(Debug)]
+        pub struct $iter<'a, T> {
+            steps: Steps<'a, T>,
+        }
+
+        impl<'a, T: Ord> Iterator for $iter<'a, T> {
+            type Item = &'a T;
+
+            fn next(&mut self) -> Option<Self::Item> {
+                self.steps.by_ref().find_map(|$step| $keep)
+            }
+        }
+
+        impl<'a, T: Ord> FusedIterator for $iter<'a, T> {}
+    };
+}
+
+set_operation!(
+    /// Returns the elements of either sorted slice, in order. An element in
+    /// both is yielded once, from `left`.
+    union,
+    Union,
+    union_into,
+    |step| match step {
+        Step::Left(x) | Step::Right(x) | Step::Both(x) => Some(x),
+    }
+);
+
+set_operation!(
+    /// Returns the elements of `left` that are also in `right`, in order.
+    intersection,
+    Intersection,
+    intersection_into,
+    |step| match step {
+        Step::Both(x) => Some(x),
+        _ => None,
+    }
+);
+
+set_operation!(
+    /// Returns the elements of `left` that are not in `right`, in order.
+    difference,
+    Difference,
+    difference_into,
+    |step| match step {
+        Step::Left(x) => Some(x),
+        _ => None,
+    }
+);
+
+set_operation!(
+    /// Returns the elements in exactly one of the sorted slices, in order.
+    symmetric_difference,
+    SymmetricDifference,
+    symmetric_difference_into,
+    |step| match step {
+        Step::Left(x) | Step::Right(x) => Some(x),
+        Step::Both(_) => None,
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use rand::distributions::Uniform;
+    use rand::Rng;
+
+    use super::*;
+
+    fn check_orderly<T: Ord>(a: &[T]) {
+        if a.is_empty() {
+            return;
+        }
+
+        for i in 0..a.len() - 1 {
+            assert!(a[i] <= a[i + 1]);
+        }
+    }
+
+    fn sorted_random(len: usize, max: i32) -> Vec<i32> {
+        let mut rng = rand::thread_rng();
+        let mut data = (&mut rng)
+            .sample_iter(Uniform::new_inclusive(0, max))
+            .take(len)
+            .collect::<Vec<i32>>();
+        data.sort();
+        data
+    }
+
+    #[test]
+    fn test_merge_into() {
+        let mut out = [0; 7];
+        merge_into(&[1, 3, 5, 7], &[2, 4, 6], &mut out);
+        assert_eq!(out, [1, 2, 3, 4, 5, 6, 7]);
+
+        let mut out = [0; 3];
+        merge_into(&[], &[1, 2, 3], &mut out);
+        assert_eq!(out, [1, 2, 3]);
+
+        let left = [(1, 'l'), (2, 'l'), (2, 'l')];
+        let right = [(0, 'r'), (2, 'r'), (3, 'r')];
+        let mut out = [(0, ' '); 6];
+        merge_into_by(&left, &right, &mut out, |x, y| x.0.cmp(&y.0));
+        assert_eq!(
+            out,
+            [(0, 'r'), (1, 'l'), (2, 'l'), (2, 'l'), (2, 'r'), (3, 'r')]
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_merge_into_wrong_length() {
+        merge_into(&[1, 2], &[3], &mut [0; 2]);
+    }
+
+    #[test]
+    fn test_merge_in_place() {
+        let mut data = [1, 3, 5, 7, 9, 0, 2, 4, 6, 8];
+        merge_in_place(&mut data, 5);
+        assert_eq!(data, [0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+
+        check_orderly(merge_in_place::<i32>(&mut [], 0));
+        check_orderly(merge_in_place(&mut [2, 1], 1));
+        check_orderly(merge_in_place(&mut [1, 2, 3], 3));
+        check_orderly(merge_in_place(&mut [1, 2, 3], 0));
+        check_orderly(merge_in_place(&mut [9, 0, 1, 2, 3, 4], 1));
+        check_orderly(merge_in_place(&mut [1, 2, 3, 4, 9, 0], 5));
+
+        for (left, right) in [(0, 500), (1, 400), (300, 7), (1000, 1000), (123, 4567)] {
+            let mut data = sorted_random(left, 50)
+                .into_iter()
+                .map(|x| (x, 'l'))
+                .chain(sorted_random(right, 50).into_iter().map(|x| (x, 'r')))
+                .collect::<Vec<(i32, char)>>();
+
+            let mut expected = data.clone();
+            expected.sort_by_key(|x| x.0);
+
+            merge_in_place_by(&mut data, left, |x, y| x.0.cmp(&y.0));
+            assert_eq!(data, expected);
+        }
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let left = [1, 2, 2, 2, 4, 6];
+        let right = [2, 2, 3, 4, 5];
+
+        assert_eq!(
+            union(&left, &right).copied().collect::<Vec<i32>>(),
+            vec![1, 2, 2, 2, 3, 4, 5, 6]
+        );
+        assert_eq!(
+            intersection(&left, &right).copied().collect::<Vec<i32>>(),
+            vec![2, 2, 4]
+        );
+        assert_eq!(
+            difference(&left, &right).copied().collect::<Vec<i32>>(),
+            vec![1, 2, 6]
+        );
+        assert_eq!(
+            symmetric_difference(&left, &right)
+                .copied()
+                .collect::<Vec<i32>>(),
+            vec![1, 2, 3, 5, 6]
+        );
+
+        assert_eq!(union::<i32>(&[], &[]).next(), None);
+        assert_eq!(difference(&[1, 2], &[]).count(), 2);
+        assert_eq!(intersection(&[], &[1, 2]).count(), 0);
+    }
+
+    #[test]
+    fn test_set_operations_stable() {
+        #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+        struct Key(i32);
+
+        // Equal keys are yielded from `left`, which the references show.
+        let left = [Key(1), Key(2)];
+        let right = [Key(2), Key(3)];
+        let from_left = union(&left, &right).filter(|x| std::ptr::eq(*x, &left[1]));
+        assert_eq!(from_left.count(), 1);
+
+        let both = intersection(&left, &right).next().unwrap();
+        assert!(std::ptr::eq(both, &left[1]));
+    }
+
+    #[test]
+    fn test_set_operations_into() {
+        let left = sorted_random(1000, 300);
+        let right = sorted_random(800, 300);
+        let mut out = vec![0; left.len() + right.len()];
+
+        // Multiset difference and intersection, the slow way.
+        let mut only_left = vec![];
+        let mut both = vec![];
+        let mut rest = right.clone();
+
+        for &x in &left {
+            match rest.iter().position(|&y| y == x) {
+                Some(i) => {
+                    rest.remove(i);
+                    both.push(x);
+                }
+                None => only_left.push(x),
+            }
+        }
+
+        let n = intersection_into(&left, &right, &mut out);
+        assert_eq!(out[..n], both);
+
+        let n = difference_into(&left, &right, &mut out);
+        assert_eq!(out[..n], only_left);
+
+        let n = symmetric_difference_into(&left, &right, &mut out);
+        let mut expected = [only_left.clone(), rest.clone()].concat();
+        expected.sort();
+        assert_eq!(out[..n], expected);
+
+        let n = union_into(&left, &right, &mut out);
+        let mut expected = [left.clone(), rest].concat();
+        expected.sort();
+        assert_eq!(out[..n], expected);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_set_operations_into_short() {
+        union_into(&[1, 2], &[3], &mut [0; 2]);
+    }
+}
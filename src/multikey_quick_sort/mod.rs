@@ -0,0 +1,181 @@
+//! # Multikey Quick Sort (3-Way Radix Quick Sort)
+//!
+//! This is adapted from
+//!
+//! <https://www.cs.princeton.edu/~rs/strings/paper.pdf>
+//!
+//! <https://en.wikipedia.org/wiki/Multi-key_quicksort>
+//!
+//! Test case is derived from
+//!
+//! <https://github.com/rust-lang/rust/blob/60bd3f96779dbe6bd206dae09395e9af7d580552/library/alloc/src/collections/binary_heap/tests.rs>
+
+use std::mem;
+
+use crate::insertion_sort::insertion_sort;
+use crate::quick_sort::{median_of_three, partition_by};
+
+/// Partitions up to this length are insertion sorted on their remaining bytes.
+const INSERTION_THRESHOLD: usize = 16;
+
+/// Sorting the entire array of strings, or byte strings, in lexicographic
+/// byte order.
+///
+/// This is the same order as [`Ord`] for `String`, `&str` and `Vec<u8>`, but
+/// the common prefixes of the elements are only looked at once per partition.
+pub fn sort<T: AsRef<[u8]>>(a: &mut [T]) -> &mut [T] {
+    multikey_quick_sort(a, 0);
+    a
+}
+
+/// Sorting the entire array of strings in descending lexicographic byte order.
+pub fn sort_descending<T: AsRef<[u8]>>(a: &mut [T]) -> &mut [T] {
+    sort(a).reverse();
+    a
+}
+
+/// Sorts `a[]`, whose elements all share their first `depth` bytes, by their
+/// byte at `depth`, then sorts the equal partition from the next byte on.
+///
+/// The two smaller partitions are sorted recursively, the largest one by the
+/// next iteration of the loop.
+fn multikey_quick_sort<T: AsRef<[u8]>>(mut a: &mut [T], mut depth: usize) {
+    loop {
+        let size = a.len();
+
+        // Small partitions are cheaper to insertion sort.
+        if size <= INSERTION_THRESHOLD {
+            insertion_sort(a, &mut |x: &T, y: &T| {
+                x.as_ref()[depth..].cmp(&y.as_ref()[depth..])
+            });
+            return;
+        }
+
+        let median = median_of_three(a, 0, size / 2, size - 1, &mut |x: &T, y: &T| {
+            byte(x, depth).cmp(&byte(y, depth))
+        });
+        let pivot = byte(&a[median], depth);
+
+        // [lt] < pivot, [eq] == pivot, [gt] > pivot at `depth`.
+        let (lt, eq, _) = partition_by(a, |x| byte(x, depth).cmp(&pivot));
+        let (left, rest) = mem::take(&mut a).split_at_mut(lt.end);
+        let (middle, right) = rest.split_at_mut(eq.len());
+
+        // Elements that end at `depth` are all equal, there is nothing left to sort.
+        let mut parts = if pivot.is_some() {
+            [(left, depth), (middle, depth + 1), (right, depth)]
+        } else {
+            [(left, depth), (&mut [][..], depth), (right, depth)]
+        };
+        parts.sort_by_key(|part| part.0.len());
+
+        let [(first, d1), (second, d2), (largest, d3)] = parts;
+        multikey_quick_sort(first, d1);
+        multikey_quick_sort(second, d2);
+
+        a = largest;
+        depth = d3;
+    }
+}
+
+/// Returns the byte of `x` at `depth`, or `None` if `x` is shorter than that,
+/// which sorts before every byte.
+#[inline]
+fn byte<T: AsRef<[u8]>>(x: &T, depth: usize) -> Option<u8> {
+    x.as_ref().get(depth).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::distributions::Uniform;
+    use rand::Rng;
+
+    use super::*;
+
+    fn check_orderly<T: Ord>(a: &[T]) {
+        if a.is_empty() {
+            return;
+        }
+
+        for i in 0..a.len() - 1 {
+            assert!(a[i] <= a[i + 1]);
+        }
+    }
+
+    #[test]
+    fn test_basic() {
+        let mut data = vec!["she", "sells", "sea", "shells"];
+        let sorted = vec!["sea", "sells", "she", "shells"];
+        sort(&mut data);
+        check_orderly(&data);
+        assert_eq!(data, sorted);
+    }
+
+    #[test]
+    fn test_batch() {
+        check_orderly(sort::<&str>(&mut []));
+        check_orderly(sort(&mut ["a"]));
+        check_orderly(sort(&mut ["b", "a"]));
+        check_orderly(sort(&mut ["a", "b"]));
+        check_orderly(sort(&mut ["", "a", ""]));
+        check_orderly(sort(&mut ["ab", "a", "abc", "", "b", "ba"]));
+        check_orderly(sort(
+            &mut "she sells sea shells by the sea shore the shells she sells are surely seashells"
+                .split(' ')
+                .collect::<Vec<&str>>(),
+        ));
+    }
+
+    #[test]
+    fn test_owned() {
+        let mut data = ["ünïcode", "zebra", "apple", "Zebra", "äpple", "app"]
+            .map(String::from)
+            .to_vec();
+        let mut sorted = data.clone();
+        sorted.sort();
+        assert_eq!(sort(&mut data), sorted);
+
+        let mut data = vec![vec![255u8, 0], vec![0], vec![], vec![0, 0], vec![255]];
+        sort(&mut data);
+        assert_eq!(
+            data,
+            vec![vec![], vec![0], vec![0, 0], vec![255], vec![255, 0]]
+        );
+
+        sort_descending(&mut data);
+        assert_eq!(
+            data,
+            vec![vec![255, 0], vec![255], vec![0, 0], vec![0], vec![]]
+        );
+    }
+
+    #[test]
+    fn test_shared_prefixes() {
+        let mut rng = rand::thread_rng();
+        let range = Uniform::new_inclusive(0, 30);
+        let mut data = (&mut rng)
+            .sample_iter(range)
+            .take(5000)
+            .map(|x| format!("https://example.com/static/assets/{}/{}", x % 3, x))
+            .collect::<Vec<String>>();
+        let mut sorted = data.clone();
+        sorted.sort();
+        assert_eq!(sort(&mut data), sorted);
+    }
+
+    #[test]
+    fn test_random() {
+        let mut rng = rand::thread_rng();
+        let length = Uniform::new_inclusive(0, 12);
+        let letter = Uniform::new_inclusive(b'a', b'd');
+        let mut data = (0..20000)
+            .map(|_| {
+                let n = rng.sample(length);
+                (&mut rng).sample_iter(letter).take(n).collect::<Vec<u8>>()
+            })
+            .collect::<Vec<Vec<u8>>>();
+        let mut sorted = data.clone();
+        sorted.sort();
+        assert_eq!(sort(&mut data), sorted);
+    }
+}
@@ -0,0 +1,650 @@
+//! # Pattern-Defeating Quick Sort
+//!
+//! This is adapted from
+//!
+//! <https://github.com/orlp/pdqsort>
+//!
+//! <https://arxiv.org/abs/2106.05123>
+//!
+//! <https://github.com/rust-lang/rust/blob/cb9467515b5a9b15aaa905683c6b4dd9e851056c/library/core/src/slice/sort.rs>
+//!
+//! Test case is derived from
+//!
+//! <https://github.com/rust-lang/rust/blob/60bd3f96779dbe6bd206dae09395e9af7d580552/library/alloc/src/collections/binary_heap/tests.rs>
+
+use std::cmp::Ordering;
+
+use crate::heap_sort;
+use crate::insertion_sort;
+use crate::quick_sort;
+
+/// Partitions up to this length are insertion sorted.
+const INSERTION_THRESHOLD: usize = 20;
+
+/// Partitions of at least this length use Tukey's ninther as the pivot.
+const NINTHER_THRESHOLD: usize = 50;
+
+/// Number of elements scanned at a time by [`partition_in_blocks`].
+const BLOCK: usize = 128;
+
+/// Sorting the entire array.
+pub fn sort<T: Ord>(a: &mut [T]) -> &mut [T] {
+    sort_by(a, T::cmp)
+}
+
+/// Sorting the entire array with a comparator function.
+pub fn sort_by<T, F>(a: &mut [T], mut compare: F) -> &mut [T]
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let size = a.len();
+
+    if size <= 1 {
+        return a;
+    }
+
+    // Number of imbalanced partitions allowed before switching to heap sort.
+    let limit = usize::BITS - size.leading_zeros();
+
+    pdq_sort(a, &mut compare, None, limit);
+    a
+}
+
+/// Sorting the entire array with a key extraction function.
+pub fn sort_by_key<T, K, F>(a: &mut [T], mut f: F) -> &mut [T]
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    sort_by(a, |x, y| f(x).cmp(&f(y)))
+}
+
+/// Sorts `a[]`, divides it into partitions, then sorts those.
+///
+/// `pred` is the pivot of the parent partition if `a[]` is its right side,
+/// so it is not greater than any element of `a[]`. `limit` is the number of
+/// imbalanced partitions allowed before switching to heap sort.
+fn pdq_sort<'a, T, F>(mut a: &'a mut [T], compare: &mut F, mut pred: Option<&'a T>, mut limit: u32)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    // Whether the last partitioning was reasonably balanced.
+    let mut was_balanced = true;
+    // Whether the last partitioning did not move any elements.
+    let mut was_partitioned = true;
+
+    loop {
+        let size = a.len();
+
+        // Small partitions are cheaper to insertion sort.
+        if size <= INSERTION_THRESHOLD {
+            insertion_sort::insertion_sort(a, compare);
+            return;
+        }
+
+        // Too many bad pivots, switch to a sort with a guaranteed worst case.
+        if limit == 0 {
+            heap_sort::heap_sort(a, compare);
+            return;
+        }
+
+        // If the last partitioning was imbalanced, shuffle some elements around
+        // to break the pattern that caused it.
+        if !was_balanced {
+            break_patterns(a);
+            limit -= 1;
+        }
+
+        // Choice of pivot.
+        let (pivot, likely_sorted) = choose_pivot(a, compare);
+
+        // If the input already looks sorted, try to finish with a few insertions.
+        if was_balanced && was_partitioned && likely_sorted && partial_insertion_sort(a, compare) {
+            return;
+        }
+
+        // If the pivot equals the parent pivot, it is the smallest element here,
+        // so move everything equal to it to the front and skip over that.
+        if let Some(p) = pred {
+            if compare(p, &a[pivot]) != Ordering::Less {
+                let mid = partition_equal(a, pivot, compare);
+                a = &mut a[mid..];
+                continue;
+            }
+        }
+
+        // Partition array and get the pivot index.
+        let (mid, partitioned) = partition(a, pivot, compare);
+        was_balanced = mid.min(size - mid) >= size / 8;
+        was_partitioned = partitioned;
+
+        let (left, right) = a.split_at_mut(mid);
+        let (pivot, right) = right.split_at_mut(1);
+        let pivot = &pivot[0];
+
+        // Sort the smaller partition, then carry on with the larger one.
+        if left.len() < right.len() {
+            pdq_sort(left, compare, pred, limit);
+            a = right;
+            pred = Some(pivot);
+        } else {
+            pdq_sort(right, compare, Some(pivot), limit);
+            a = left;
+        }
+    }
+}
+
+/// Returns the index of a pivot, median-of-three for short arrays and
+/// Tukey's ninther for long ones, and whether `a[]` looks already sorted.
+///
+/// If the samples were all in descending order, `a[]` is reversed first, since
+/// then it is likely sorted in descending order.
+fn choose_pivot<T, F>(a: &mut [T], compare: &mut F) -> (usize, bool)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let size = a.len();
+    let (i, j, k) = (size / 4, size / 4 * 2, size / 4 * 3);
+
+    // Count the sampled pairs that are out of order.
+    let mut swaps = 0;
+    let mut counted = |x: &T, y: &T| {
+        let order = compare(x, y);
+        if order == Ordering::Less {
+            swaps += 1;
+        }
+        order
+    };
+
+    let (pivot, max_swaps) = if size >= NINTHER_THRESHOLD {
+        (quick_sort::ninther(a, i, j, k, &mut counted), 4 * 3)
+    } else {
+        (quick_sort::median_of_three(a, i, j, k, &mut counted), 3)
+    };
+
+    if swaps == 0 {
+        (pivot, true)
+    } else if swaps == max_swaps {
+        a.reverse();
+        (size - 1 - pivot, true)
+    } else {
+        (pivot, false)
+    }
+}
+
+/// Sorts `a[]` if only a few elements are out of place, and returns whether it succeeded.
+fn partial_insertion_sort<T, F>(a: &mut [T], compare: &mut F) -> bool
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    // Maximum number of adjacent out-of-order pairs that get shifted.
+    const MAX_STEPS: usize = 5;
+    // Arrays shorter than this are not worth shifting.
+    const SHORTEST_SHIFTING: usize = 50;
+
+    let size = a.len();
+    let mut i = 1;
+
+    for _ in 0..MAX_STEPS {
+        // Find the next pair of adjacent out-of-order elements.
+        while i < size && compare(&a[i], &a[i - 1]) != Ordering::Less {
+            i += 1;
+        }
+
+        // Are we done?
+        if i == size {
+            return true;
+        }
+
+        if size < SHORTEST_SHIFTING {
+            return false;
+        }
+
+        // Swap the found pair, then shift both into place.
+        a.swap(i - 1, i);
+        shift_tail(&mut a[..i], compare);
+        shift_head(&mut a[i..], compare);
+    }
+
+    false
+}
+
+/// Moves the last element left until it is not less than its predecessor.
+fn shift_tail<T, F>(a: &mut [T], compare: &mut F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let mut j = a.len().saturating_sub(1);
+
+    while j > 0 && compare(&a[j], &a[j - 1]) == Ordering::Less {
+        a.swap(j, j - 1);
+        j -= 1;
+    }
+}
+
+/// Moves the first element right until it is not greater than its successor.
+fn shift_head<T, F>(a: &mut [T], compare: &mut F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let mut j = 0;
+
+    while j + 1 < a.len() && compare(&a[j + 1], &a[j]) == Ordering::Less {
+        a.swap(j, j + 1);
+        j += 1;
+    }
+}
+
+/// Swaps a few elements around the middle with pseudo-random ones, to break
+/// patterns that keep producing imbalanced partitions.
+///
+/// The swaps are deterministic, seeded by the length of `a[]`.
+fn break_patterns<T>(a: &mut [T]) {
+    let size = a.len();
+
+    if size < 8 {
+        return;
+    }
+
+    // Xorshift RNG, from "Xorshift RNGs" by George Marsaglia.
+    let mut random = size as u64;
+    let mut next = || {
+        random ^= random << 13;
+        random ^= random >> 7;
+        random ^= random << 17;
+        random as usize
+    };
+
+    let modulus = size.next_power_of_two();
+    let pos = size / 4 * 2;
+
+    for i in 0..3 {
+        let mut other = next() & (modulus - 1);
+
+        if other >= size {
+            other -= size;
+        }
+
+        a.swap(pos - 1 + i, other);
+    }
+}
+
+/// Divides array into elements less than `a[pivot]`, followed by elements
+/// greater than or equal to it, and returns the new pivot index, and whether
+/// `a[]` was already partitioned.
+fn partition<T, F>(a: &mut [T], pivot: usize, compare: &mut F) -> (usize, bool)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    // Move the pivot out of the way, to the front.
+    a.swap(0, pivot);
+
+    let (mid, was_partitioned) = {
+        let (pivot, rest) = a.split_at_mut(1);
+        let pivot = &pivot[0];
+
+        // Skip over the elements that are already on the correct side.
+        let mut l = 0;
+        let mut r = rest.len();
+
+        while l < r && compare(&rest[l], pivot) == Ordering::Less {
+            l += 1;
+        }
+
+        while l < r && compare(&rest[r - 1], pivot) != Ordering::Less {
+            r -= 1;
+        }
+
+        (
+            l + partition_in_blocks(&mut rest[l..r], pivot, compare),
+            l >= r,
+        )
+    };
+
+    // Move the pivot between the two partitions.
+    a.swap(0, mid);
+
+    (mid, was_partitioned)
+}
+
+/// Divides array into elements less than `pivot`, followed by elements
+/// greater than or equal to it, and returns the number of the former.
+///
+/// This is the BlockQuicksort scheme: the comparisons of a whole block are
+/// recorded as offsets first, without branching on their result, and the
+/// misplaced elements are swapped afterwards.
+fn partition_in_blocks<T, F>(a: &mut [T], pivot: &T, compare: &mut F) -> usize
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    // The left block starts at `l`, the right block ends at `r`.
+    let mut l = 0;
+    let mut r = a.len();
+
+    // Offsets of the misplaced elements of each block, `[start, end)` are pending.
+    let mut block_l = BLOCK;
+    let mut offsets_l = [0u8; BLOCK];
+    let mut start_l = 0;
+    let mut end_l = 0;
+
+    let mut block_r = BLOCK;
+    let mut offsets_r = [0u8; BLOCK];
+    let mut start_r = 0;
+    let mut end_r = 0;
+
+    loop {
+        // Is this the last round?
+        let is_done = r - l <= 2 * BLOCK;
+
+        if is_done {
+            // Shrink the blocks so they cover the remaining gap exactly.
+            let mut rem = r - l;
+
+            if start_l < end_l || start_r < end_r {
+                rem -= BLOCK;
+            }
+
+            if start_l < end_l {
+                block_r = rem;
+            } else if start_r < end_r {
+                block_l = rem;
+            } else {
+                block_l = rem / 2;
+                block_r = rem - block_l;
+            }
+        }
+
+        // Scan the left block for elements that are not less than the pivot.
+        if start_l == end_l {
+            start_l = 0;
+            end_l = 0;
+
+            for i in 0..block_l {
+                offsets_l[end_l] = i as u8;
+                end_l += (compare(&a[l + i], pivot) != Ordering::Less) as usize;
+            }
+        }
+
+        // Scan the right block for elements that are less than the pivot.
+        if start_r == end_r {
+            start_r = 0;
+            end_r = 0;
+
+            for i in 0..block_r {
+                offsets_r[end_r] = i as u8;
+                end_r += (compare(&a[r - 1 - i], pivot) == Ordering::Less) as usize;
+            }
+        }
+
+        // Swap misplaced elements between the two blocks.
+        let count = (end_l - start_l).min(end_r - start_r);
+
+        for _ in 0..count {
+            a.swap(
+                l + offsets_l[start_l] as usize,
+                r - 1 - offsets_r[start_r] as usize,
+            );
+            start_l += 1;
+            start_r += 1;
+        }
+
+        // Move on from the blocks that have been fully dealt with.
+        if start_l == end_l {
+            l += block_l;
+        }
+
+        if start_r == end_r {
+            r -= block_r;
+        }
+
+        if is_done {
+            break;
+        }
+    }
+
+    if start_l < end_l {
+        // The left block remains, move its misplaced elements to the far right.
+        while start_l < end_l {
+            end_l -= 1;
+            r -= 1;
+            a.swap(l + offsets_l[end_l] as usize, r);
+        }
+        r
+    } else if start_r < end_r {
+        // The right block remains, move its misplaced elements to the far left.
+        while start_r < end_r {
+            end_r -= 1;
+            a.swap(l, r - 1 - offsets_r[end_r] as usize);
+            l += 1;
+        }
+        l
+    } else {
+        l
+    }
+}
+
+/// Divides array into elements equal to `a[pivot]`, followed by elements
+/// greater than it, and returns the number of the former.
+///
+/// `a[]` must not contain elements less than `a[pivot]`.
+fn partition_equal<T, F>(a: &mut [T], pivot: usize, compare: &mut F) -> usize
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    // Move the pivot out of the way, to the front.
+    a.swap(0, pivot);
+
+    let (pivot, rest) = a.split_at_mut(1);
+    let pivot = &pivot[0];
+
+    let mut l = 0;
+    let mut r = rest.len();
+
+    loop {
+        while l < r && compare(pivot, &rest[l]) != Ordering::Less {
+            l += 1;
+        }
+
+        while l < r && compare(pivot, &rest[r - 1]) == Ordering::Less {
+            r -= 1;
+        }
+
+        if l >= r {
+            break;
+        }
+
+        r -= 1;
+        rest.swap(l, r);
+        l += 1;
+    }
+
+    // Count the pivot itself, too.
+    l + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::distributions::Uniform;
+    use rand::Rng;
+
+    use super::*;
+
+    fn check_orderly<T: Ord>(a: &[T]) {
+        if a.is_empty() {
+            return;
+        }
+
+        for i in 0..a.len() - 1 {
+            assert!(a[i] <= a[i + 1]);
+        }
+    }
+
+    fn count_comparisons(a: &mut [usize]) -> usize {
+        let mut count = 0;
+        sort_by(a, |x, y| {
+            count += 1;
+            x.cmp(y)
+        });
+        check_orderly(a);
+        count
+    }
+
+    /// Builds an input that is as bad as possible for [`sort`], using McIlroy's
+    /// adversary from "A Killer Adversary for Quicksort".
+    fn killer_input(size: usize) -> Vec<usize> {
+        let gas = size;
+        let mut val = vec![gas; size];
+        let mut solid = 0;
+        let mut candidate = 0;
+
+        let mut indices = (0..size).collect::<Vec<usize>>();
+        sort_by(&mut indices, |&x, &y| {
+            if val[x] == gas && val[y] == gas {
+                if x == candidate {
+                    val[x] = solid;
+                } else {
+                    val[y] = solid;
+                }
+                solid += 1;
+            }
+
+            if val[x] == gas {
+                candidate = x;
+            } else if val[y] == gas {
+                candidate = y;
+            }
+
+            val[x].cmp(&val[y])
+        });
+
+        val
+    }
+
+    #[test]
+    fn test_basic() {
+        let mut data = vec![5, 9, 3];
+        let sorted = vec![3, 5, 9];
+        sort(&mut data);
+        check_orderly(&data);
+        assert_eq!(data, sorted);
+    }
+
+    #[test]
+    fn test_normal() {
+        let mut data = vec![2, 4, 6, 2, 1, 8, 10, 3, 5, 7, 0, 9, 1];
+        let sorted = vec![0, 1, 1, 2, 2, 3, 4, 5, 6, 7, 8, 9, 10];
+        sort(&mut data);
+        check_orderly(&data);
+        assert_eq!(data, sorted);
+    }
+
+    #[test]
+    fn test_batch() {
+        check_orderly(sort::<i32>(&mut []));
+        check_orderly(sort(&mut [5]));
+        check_orderly(sort(&mut [3, 2]));
+        check_orderly(sort(&mut [2, 3]));
+        check_orderly(sort(&mut [5, 1, 2]));
+        check_orderly(sort(&mut [1, 100, 2, 3]));
+        check_orderly(sort(&mut [1, 3, 5, 7, 9, 2, 4, 6, 8, 0]));
+        check_orderly(sort(&mut [2, 4, 6, 2, 1, 8, 10, 3, 5, 7, 0, 9, 1]));
+        check_orderly(sort(&mut [
+            9, 11, 9, 9, 9, 9, 11, 2, 3, 4, 11, 9, 0, 0, 0, 0,
+        ]));
+        check_orderly(sort(&mut [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10]));
+        check_orderly(sort(&mut [10, 9, 8, 7, 6, 5, 4, 3, 2, 1, 0]));
+        check_orderly(sort(&mut [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 0, 0, 0, 1, 2]));
+        check_orderly(sort(&mut [5, 4, 3, 2, 1, 5, 4, 3, 2, 1, 5, 4, 3, 2, 1]));
+    }
+
+    #[test]
+    fn test_patterns() {
+        let size = 10000;
+        let patterns: [fn(usize) -> usize; 7] = [
+            // Sorted.
+            |i| i,
+            // Reverse sorted.
+            |i| 10000 - i,
+            // Sawtooth.
+            |i| i % 100,
+            // Reverse sawtooth.
+            |i| 100 - i % 100,
+            // Organ pipe.
+            |i| if i < 5000 { i } else { 10000 - i },
+            // Few unique.
+            |i| i * 7919 % 4,
+            // All equal.
+            |_| 42,
+        ];
+
+        for pattern in patterns {
+            let mut data = (0..size).map(pattern).collect::<Vec<usize>>();
+            let mut sorted = data.clone();
+            sorted.sort();
+            sort(&mut data);
+            assert_eq!(data, sorted);
+        }
+    }
+
+    #[test]
+    fn test_sorted_is_linear() {
+        let size = 10000;
+
+        let mut data = (0..size).collect::<Vec<usize>>();
+        assert!(count_comparisons(&mut data) < 2 * size);
+
+        let mut data = (0..size).rev().collect::<Vec<usize>>();
+        assert!(count_comparisons(&mut data) < 2 * size);
+    }
+
+    #[test]
+    fn test_killer() {
+        let size = 5000;
+        let mut data = killer_input(size);
+        assert!(count_comparisons(&mut data) < 4 * size * 13);
+    }
+
+    #[test]
+    fn test_random() {
+        let mut rng = rand::thread_rng();
+
+        for max in [1, 10, 1000, 50000] {
+            let range = Uniform::new_inclusive(-max, max);
+            let mut data = (&mut rng)
+                .sample_iter(range)
+                .take(50000)
+                .collect::<Vec<i32>>();
+            sort(&mut data);
+            check_orderly(&data);
+        }
+    }
+
+    #[test]
+    fn test_random_lengths() {
+        let mut rng = rand::thread_rng();
+        let range = Uniform::new_inclusive(0, 100);
+
+        for size in (0..600).step_by(7) {
+            let mut data = (&mut rng)
+                .sample_iter(range)
+                .take(size)
+                .collect::<Vec<i32>>();
+            let mut sorted = data.clone();
+            sorted.sort();
+            sort(&mut data);
+            assert_eq!(data, sorted);
+        }
+    }
+
+    #[test]
+    fn test_sort_by_key() {
+        let mut data = ["pear", "apple", "fig", "banana", "cherry", "date"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<String>>();
+        sort_by_key(&mut data, |s| s.len());
+        assert_eq!(data[0], "fig");
+        assert_eq!(data[5].len(), 6);
+        check_orderly(&data.iter().map(|s| s.len()).collect::<Vec<usize>>());
+    }
+}
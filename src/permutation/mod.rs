@@ -0,0 +1,322 @@
+//! # Argsort And Permutations
+//!
+//! This is adapted from
+//!
+//! <https://numpy.org/doc/stable/reference/generated/numpy.argsort.html>
+//!
+//! <https://en.wikipedia.org/wiki/Permutation#Cycle_notation>
+//!
+//! <https://en.wikipedia.org/wiki/Ranking#Ranking_in_statistics>
+//!
+//! Test case is derived from
+//!
+//! <https://github.com/rust-lang/rust/blob/60bd3f96779dbe6bd206dae09395e9af7d580552/library/alloc/src/collections/binary_heap/tests.rs>
+
+use std::cmp::Ordering;
+
+use crate::merge_sort;
+use crate::pdqsort;
+
+/// How [`rank`] ranks equal elements.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RankMethod {
+    /// The ranks of distinct values are consecutive: `[10, 20, 20, 30]` ranks `[1, 2, 2, 3]`.
+    Dense,
+    /// Equal elements take the least of their ranks: `[10, 20, 20, 30]` ranks `[1, 2, 2, 4]`.
+    Min,
+    /// Equal elements take the mean of their ranks: `[10, 20, 20, 30]` ranks `[1, 2.5, 2.5, 4]`.
+    Average,
+}
+
+/// Returns the indices that would sort the array, without reordering it.
+///
+/// Equal elements keep their original order, as with [`merge_sort::sort`].
+pub fn argsort<T: Ord>(a: &[T]) -> Vec<usize> {
+    argsort_by(a, T::cmp)
+}
+
+/// Returns the indices that would sort the array with a comparator function.
+///
+/// Equal elements keep their original order.
+pub fn argsort_by<T, F>(a: &[T], mut compare: F) -> Vec<usize>
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let mut indices = (0..a.len()).collect::<Vec<usize>>();
+    merge_sort::sort_by(&mut indices, |&i, &j| compare(&a[i], &a[j]));
+    indices
+}
+
+/// Returns the indices that would sort the array with a key extraction function.
+///
+/// Equal elements keep their original order.
+pub fn argsort_by_key<T, K, F>(a: &[T], mut f: F) -> Vec<usize>
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    argsort_by(a, |x, y| f(x).cmp(&f(y)))
+}
+
+/// Returns the indices that would sort the array, in any order among equal
+/// elements, as with [`pdqsort::sort`].
+pub fn argsort_unstable<T: Ord>(a: &[T]) -> Vec<usize> {
+    argsort_unstable_by(a, T::cmp)
+}
+
+/// Returns the indices that would sort the array with a comparator function,
+/// in any order among equal elements.
+pub fn argsort_unstable_by<T, F>(a: &[T], mut compare: F) -> Vec<usize>
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let mut indices = (0..a.len()).collect::<Vec<usize>>();
+    pdqsort::sort_by(&mut indices, |&i, &j| compare(&a[i], &a[j]));
+    indices
+}
+
+/// Reorders the array so that `a[i]` is the element that was at `a[perm[i]]`,
+/// following the cycles of the permutation with one swap per element.
+///
+/// Applying the result of [`argsort`] sorts the array, and the same permutation
+/// can be applied to any number of parallel arrays.
+///
+/// The elements are moved in place, but not all the bookkeeping is: `perm` is
+/// borrowed and cannot be marked, so an auxiliary `Vec<bool>` of `a.len()`
+/// entries records the indices checked and then the elements placed.
+///
+/// # Panics
+///
+/// Panics if `perm` is not a permutation of the indices of `a[]`; `a[]` is left
+/// untouched then.
+pub fn apply_permutation_in_place<T>(a: &mut [T], perm: &[usize]) {
+    assert_eq!(a.len(), perm.len(), "permutation length mismatch");
+
+    // The marks of the check are reused, so this is the only allocation.
+    let mut visited = check_permutation(perm);
+    visited.fill(false);
+
+    for start in 0..a.len() {
+        if visited[start] {
+            continue;
+        }
+
+        // Each swap moves one element into its final place, and carries the
+        // element that was at `start` along the cycle.
+        let mut i = start;
+
+        loop {
+            visited[i] = true;
+            let next = perm[i];
+
+            if next == start {
+                break;
+            }
+
+            a.swap(i, next);
+            i = next;
+        }
+    }
+}
+
+/// Returns the permutation that undoes `perm`: `inverse[perm[i]] == i`.
+///
+/// The inverse of [`argsort`] gives the position of each element in sorted order.
+///
+/// # Panics
+///
+/// Panics if `perm` is not a permutation of `0..perm.len()`.
+pub fn inverse_permutation(perm: &[usize]) -> Vec<usize> {
+    check_permutation(perm);
+
+    let mut inverse = vec![0; perm.len()];
+
+    for (i, &p) in perm.iter().enumerate() {
+        inverse[p] = i;
+    }
+
+    inverse
+}
+
+/// Returns the rank of every element, starting from 1, with equal elements
+/// ranked by `method`.
+pub fn rank<T: Ord>(a: &[T], method: RankMethod) -> Vec<f64> {
+    rank_by(a, method, T::cmp)
+}
+
+/// Returns the rank of every element with a comparator function, starting from
+/// 1, with equal elements ranked by `method`.
+pub fn rank_by<T, F>(a: &[T], method: RankMethod, mut compare: F) -> Vec<f64>
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let order = argsort_unstable_by(a, &mut compare);
+    let mut ranks = vec![0.0; a.len()];
+    let mut distinct = 0;
+    let mut begin = 0;
+
+    // Rank each group of equal elements `order[begin..end]` at once.
+    while begin < order.len() {
+        let mut end = begin + 1;
+
+        while end < order.len() && compare(&a[order[begin]], &a[order[end]]) == Ordering::Equal {
+            end += 1;
+        }
+
+        distinct += 1;
+
+        let value = match method {
+            RankMethod::Dense => distinct as f64,
+            RankMethod::Min => (begin + 1) as f64,
+            RankMethod::Average => (begin + 1 + end) as f64 / 2.0,
+        };
+
+        for &i in &order[begin..end] {
+            ranks[i] = value;
+        }
+
+        begin = end;
+    }
+
+    ranks
+}
+
+/// Checks that `perm` is a permutation of `0..perm.len()`, and returns the
+/// marks of the indices it has seen, which are all set.
+fn check_permutation(perm: &[usize]) -> Vec<bool> {
+    let mut seen = vec![false; perm.len()];
+
+    for &p in perm {
+        assert!(
+            p < perm.len() && !seen[p],
+            "not a permutation: index {p} is out of range or repeated"
+        );
+        seen[p] = true;
+    }
+
+    seen
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::distributions::Uniform;
+    use rand::Rng;
+
+    use super::*;
+
+    #[test]
+    fn test_argsort() {
+        let data = vec![30, 10, 20, 10];
+        assert_eq!(argsort(&data), vec![1, 3, 2, 0]);
+        assert_eq!(argsort_by(&data, |x, y| y.cmp(x)), vec![0, 2, 1, 3]);
+        assert_eq!(argsort_by_key(&data, |x| x % 20), vec![2, 0, 1, 3]);
+
+        let mut unstable = argsort_unstable(&data);
+        assert_eq!(unstable[2..], [2, 0]);
+        unstable[..2].sort();
+        assert_eq!(unstable[..2], [1, 3]);
+
+        assert!(argsort::<i32>(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_argsort_random() {
+        let mut rng = rand::thread_rng();
+        let range = Uniform::new_inclusive(0, 100);
+        let data = (&mut rng)
+            .sample_iter(range)
+            .take(10000)
+            .collect::<Vec<i32>>();
+
+        let mut expected = (0..data.len()).collect::<Vec<usize>>();
+        expected.sort_by_key(|&i| data[i]);
+        assert_eq!(argsort(&data), expected);
+
+        let unstable = argsort_unstable(&data);
+        assert!(unstable.windows(2).all(|w| data[w[0]] <= data[w[1]]));
+        assert_eq!(inverse_permutation(&unstable).len(), data.len());
+    }
+
+    #[test]
+    fn test_apply_permutation() {
+        // Sort parallel columns by the first one.
+        let mut names = vec!["carol", "alice", "dave", "bob"];
+        let mut ages = vec![35, 30, 40, 25];
+        let order = argsort(&names);
+
+        apply_permutation_in_place(&mut names, &order);
+        apply_permutation_in_place(&mut ages, &order);
+        assert_eq!(names, vec!["alice", "bob", "carol", "dave"]);
+        assert_eq!(ages, vec![30, 25, 35, 40]);
+
+        let mut data = (0..10).map(|x| x.to_string()).collect::<Vec<String>>();
+        let perm = [3, 0, 1, 2, 4, 9, 8, 7, 6, 5];
+        apply_permutation_in_place(&mut data, &perm);
+        assert_eq!(
+            data,
+            perm.iter().map(|x| x.to_string()).collect::<Vec<String>>()
+        );
+    }
+
+    #[test]
+    fn test_apply_permutation_random() {
+        let mut rng = rand::thread_rng();
+        let range = Uniform::new_inclusive(-1000, 1000);
+        let data = (&mut rng)
+            .sample_iter(range)
+            .take(5000)
+            .collect::<Vec<i32>>();
+
+        let order = argsort(&data);
+        let mut sorted = data.clone();
+        apply_permutation_in_place(&mut sorted, &order);
+
+        let mut expected = data.clone();
+        expected.sort();
+        assert_eq!(sorted, expected);
+
+        // Sending each element back to where it came from restores the array.
+        apply_permutation_in_place(&mut sorted, &inverse_permutation(&order));
+        assert_eq!(sorted, data);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_apply_not_a_permutation() {
+        apply_permutation_in_place(&mut [1, 2, 3], &[0, 2, 2]);
+    }
+
+    #[test]
+    fn test_inverse_permutation() {
+        assert_eq!(inverse_permutation(&[2, 0, 1]), vec![1, 2, 0]);
+        assert_eq!(inverse_permutation(&[]), Vec::<usize>::new());
+
+        let perm = argsort(&[5, 3, 9, 1]);
+        assert_eq!(inverse_permutation(&inverse_permutation(&perm)), perm);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_inverse_out_of_range() {
+        inverse_permutation(&[0, 3, 1]);
+    }
+
+    #[test]
+    fn test_rank() {
+        let data = [10, 20, 20, 30];
+        assert_eq!(rank(&data, RankMethod::Dense), vec![1.0, 2.0, 2.0, 3.0]);
+        assert_eq!(rank(&data, RankMethod::Min), vec![1.0, 2.0, 2.0, 4.0]);
+        assert_eq!(rank(&data, RankMethod::Average), vec![1.0, 2.5, 2.5, 4.0]);
+
+        let data = ["b", "a", "c", "a", "b", "a"];
+        assert_eq!(
+            rank(&data, RankMethod::Average),
+            vec![4.5, 2.0, 6.0, 2.0, 4.5, 2.0]
+        );
+        assert_eq!(
+            rank_by(&data, RankMethod::Dense, |x, y| y.cmp(x)),
+            vec![2.0, 3.0, 1.0, 3.0, 2.0, 3.0]
+        );
+        assert!(rank::<i32>(&[], RankMethod::Min).is_empty());
+    }
+}
@@ -6,65 +6,722 @@
 //!
 //! <https://en.wikipedia.org/wiki/Dutch_national_flag_problem>
 //!
+//! <https://en.wikipedia.org/wiki/Introsort>
+//!
+//! <https://web.archive.org/web/20151002230717/http://iaroslavski.narod.ru/quicksort/DualPivotQuicksort.pdf>
+//!
+//! <https://en.wikipedia.org/wiki/Quickselect>
+//!
+//! <https://en.wikipedia.org/wiki/Median_of_medians>
+//!
 //! Test case is derived from
 //!
 //! <https://github.com/rust-lang/rust/blob/60bd3f96779dbe6bd206dae09395e9af7d580552/library/alloc/src/collections/binary_heap/tests.rs>
 
 use std::cmp::Ordering;
+use std::ops::Range;
+use std::thread;
+
+use crate::heap_sort::heap_sort;
+use crate::insertion_sort::{insertion_sort, SortOptions};
+
+/// Partitions up to this length are insertion sorted by [`introsort`].
+const INSERTION_THRESHOLD: usize = 16;
+
+/// Partitioning rounds that may fail to shrink the range by a quarter before
+/// [`select_nth`] switches to median-of-medians pivots.
+const SELECT_BAD_ROUNDS: u32 = 4;
+
+/// Partitions up to this length are sorted on the current thread by [`par_sort`].
+const PAR_THRESHOLD: usize = 1 << 13;
+
+/// Partitions shorter than this use median-of-three instead of
+/// [`PivotStrategy::Ninther`].
+const NINTHER_THRESHOLD: usize = 50;
+
+/// How [`sort_with`] chooses the pivot of each partition.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PivotStrategy {
+    /// The first element. Quadratic on sorted input.
+    First,
+    /// The last element. Quadratic on sorted input.
+    Last,
+    /// The median of the first, middle and last elements.
+    #[default]
+    MedianOfThree,
+    /// Tukey's ninther, the median of three medians-of-three.
+    Ninther,
+    /// A uniformly random element, from a generator seeded with the given value.
+    Random(u64),
+    /// The median of the medians of groups of five, with a guaranteed split.
+    MedianOfMedians,
+}
 
 /// Sorting the entire array.
+///
+/// Like [`introsort`], it falls back to heap sort after too many bad pivots,
+/// so the worst case is `O(n log n)`.
 pub fn sort<T: Ord>(a: &mut [T]) -> &mut [T] {
+    sort_by(a, T::cmp)
+}
+
+/// Sorting the entire array in descending order.
+pub fn sort_descending<T: Ord>(a: &mut [T]) -> &mut [T] {
+    sort_by(a, |x, y| y.cmp(x))
+}
+
+/// Sorting the entire array with a comparator function.
+pub fn sort_by<T, F>(a: &mut [T], compare: F) -> &mut [T]
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    sort_by_with_options(a, SortOptions::default(), compare)
+}
+
+/// Sorting the entire array, tuned by `options`.
+pub fn sort_with_options<T: Ord>(a: &mut [T], options: SortOptions) -> &mut [T] {
+    sort_by_with_options(a, options, T::cmp)
+}
+
+/// Sorting the entire array with a comparator function, tuned by `options`.
+pub fn sort_by_with_options<T, F>(a: &mut [T], options: SortOptions, mut compare: F) -> &mut [T]
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let size = a.len();
+
+    if size <= 1 {
+        return a;
+    }
+
+    let limit = 2 * (usize::BITS - size.leading_zeros());
+    intro_sort(
+        a,
+        0,
+        size - 1,
+        options.insertion_threshold,
+        limit,
+        &mut compare,
+    );
+    a
+}
+
+/// Sorting the entire array, choosing pivots with `strategy`.
+pub fn sort_with<T: Ord>(a: &mut [T], strategy: PivotStrategy) -> &mut [T] {
+    sort_with_by(a, strategy, T::cmp)
+}
+
+/// Sorting the entire array with a comparator function, choosing pivots with `strategy`.
+pub fn sort_with_by<T, F>(a: &mut [T], strategy: PivotStrategy, mut compare: F) -> &mut [T]
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
     let size = a.len();
 
     if size <= 1 {
         return a;
     }
 
-    quick_sort(a, 0, size - 1);
+    let threshold = SortOptions::default().insertion_threshold;
+    let mut pivoter = Pivoter::new(strategy);
+    quick_sort(a, 0, size - 1, threshold, &mut pivoter, &mut compare);
     a
 }
 
+/// Sorting the entire array with a key extraction function.
+pub fn sort_by_key<T, K, F>(a: &mut [T], mut f: F) -> &mut [T]
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    sort_by(a, |x, y| f(x).cmp(&f(y)))
+}
+
+/// Sorting the entire array with a key extraction function, in descending order.
+pub fn sort_by_key_descending<T, K, F>(a: &mut [T], mut f: F) -> &mut [T]
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    sort_by(a, |x, y| f(y).cmp(&f(x)))
+}
+
+/// Sorting the entire array, with a guaranteed `O(n log n)` worst case.
+///
+/// Once the recursion gets deeper than `2 * log2(n)`, the remaining partition
+/// is heap sorted instead, and small partitions are insertion sorted.
+pub fn introsort<T: Ord>(a: &mut [T]) -> &mut [T] {
+    introsort_by(a, T::cmp)
+}
+
+/// Sorting the entire array with a comparator function, with a guaranteed
+/// `O(n log n)` worst case.
+pub fn introsort_by<T, F>(a: &mut [T], mut compare: F) -> &mut [T]
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let size = a.len();
+
+    if size <= 1 {
+        return a;
+    }
+
+    let limit = 2 * (usize::BITS - size.leading_zeros());
+    intro_sort(a, 0, size - 1, INSERTION_THRESHOLD, limit, &mut compare);
+    a
+}
+
+/// Sorting the entire array with Yaroslavskiy's dual-pivot quick sort.
+pub fn sort_dual_pivot<T: Ord>(a: &mut [T]) -> &mut [T] {
+    sort_dual_pivot_by(a, T::cmp)
+}
+
+/// Sorting the entire array with Yaroslavskiy's dual-pivot quick sort and a
+/// comparator function.
+pub fn sort_dual_pivot_by<T, F>(a: &mut [T], mut compare: F) -> &mut [T]
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let size = a.len();
+
+    if size <= 1 {
+        return a;
+    }
+
+    dual_pivot_quick_sort(a, 0, size - 1, &mut compare);
+    a
+}
+
+/// Sorting the entire array on multiple threads.
+pub fn par_sort<T: Ord + Send>(a: &mut [T]) -> &mut [T] {
+    par_sort_by(a, T::cmp)
+}
+
+/// Sorting the entire array on multiple threads with a comparator function.
+pub fn par_sort_by<T, F>(a: &mut [T], compare: F) -> &mut [T]
+where
+    T: Send,
+    F: Fn(&T, &T) -> Ordering + Sync,
+{
+    // Split into about twice as many tasks as there are threads.
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let depth = usize::BITS - threads.leading_zeros();
+
+    par_quick_sort(a, depth, &compare);
+    a
+}
+
+/// Reorders the array so that `a[k]` is the element that would be there if the
+/// array were sorted, every element before it is less than or equal to it, and
+/// every element after it is greater than or equal to it.
+///
+/// Runs in expected linear time, and in linear time in the worst case.
+///
+/// # Panics
+///
+/// Panics if `k >= a.len()`.
+pub fn select_nth<T: Ord>(a: &mut [T], k: usize) -> &mut T {
+    select_nth_by(a, k, T::cmp)
+}
+
+/// Like [`select_nth`], with a comparator function.
+pub fn select_nth_by<T, F>(a: &mut [T], k: usize, mut compare: F) -> &mut T
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let size = a.len();
+
+    assert!(
+        k < size,
+        "index {k} out of range for slice of length {size}"
+    );
+
+    select(a, 0, size - 1, k, false, &mut compare);
+    &mut a[k]
+}
+
+/// Divides the array into the elements less than, equal to, and greater than
+/// `pivot`, and returns those three ranges.
+pub fn partition_three_way<T: Ord>(
+    a: &mut [T],
+    pivot: &T,
+) -> (Range<usize>, Range<usize>, Range<usize>) {
+    partition_by(a, |x| x.cmp(pivot))
+}
+
+/// Divides the array into the elements for which `f` returns [`Ordering::Less`],
+/// [`Ordering::Equal`] and [`Ordering::Greater`], and returns those three ranges.
+///
+/// `f` is called exactly once for every element.
+pub fn partition_by<T, F>(a: &mut [T], mut f: F) -> (Range<usize>, Range<usize>, Range<usize>)
+where
+    F: FnMut(&T) -> Ordering,
+{
+    // Temporary pivot indices.
+    let mut i = 0;
+    let mut j = 0;
+    let mut k = a.len();
+
+    // [0, i) < pivot;
+    // [i, j) == pivot;
+    // [j, k) not yet sorted;
+    // [k, len) > pivot;
+    while j < k {
+        match f(&a[j]) {
+            Ordering::Less => {
+                a.swap(i, j);
+                i += 1;
+                j += 1;
+            }
+            Ordering::Greater => {
+                k -= 1;
+                a.swap(j, k);
+            }
+            Ordering::Equal => {
+                j += 1;
+            }
+        }
+    }
+
+    (0..i, i..j, j..a.len())
+}
+
 /// Sorts a (portion of an) array, divides it into partitions, then sorts those.
-fn quick_sort<T: Ord>(a: &mut [T], lo: usize, hi: usize) {
+///
+/// Only the smaller partition is sorted recursively, the larger one is sorted
+/// by the next iteration of the loop, so the recursion is at most `log2(n)` deep.
+/// Partitions up to `threshold` long are insertion sorted.
+fn quick_sort<T, F>(
+    a: &mut [T],
+    mut lo: usize,
+    mut hi: usize,
+    threshold: usize,
+    pivoter: &mut Pivoter,
+    compare: &mut F,
+) where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    // Ensure indices are in correct order.
+    while lo < hi {
+        // Small partitions are cheaper to insertion sort.
+        if hi - lo < threshold {
+            insertion_sort(&mut a[lo..=hi], compare);
+            return;
+        }
+
+        // Choice of pivot.
+        pivoter.choose(a, lo, hi, compare);
+
+        // Partition array and get the pivot indices.
+        let (left, right) = partition(a, lo, hi, compare);
+
+        // Sort the smaller partition, then carry on with the larger one.
+        if left - lo < hi - right {
+            // Left side of pivot.
+            if left > lo {
+                quick_sort(a, lo, left - 1, threshold, pivoter, compare);
+            }
+            lo = right + 1;
+        } else {
+            // Right side of pivot.
+            quick_sort(a, right + 1, hi, threshold, pivoter, compare);
+
+            if left == lo {
+                return;
+            }
+            hi = left - 1;
+        }
+    }
+}
+
+/// Like [`quick_sort`] with median-of-three pivots, but gives up on
+/// partitioning after `limit` levels.
+fn intro_sort<T, F>(
+    a: &mut [T],
+    mut lo: usize,
+    mut hi: usize,
+    threshold: usize,
+    mut limit: u32,
+    compare: &mut F,
+) where
+    F: FnMut(&T, &T) -> Ordering,
+{
     // Ensure indices are in correct order.
-    if lo >= hi {
+    while lo < hi {
+        // Small partitions are cheaper to insertion sort.
+        if hi - lo < threshold {
+            insertion_sort(&mut a[lo..=hi], compare);
+            return;
+        }
+
+        // Too many bad pivots, switch to a sort with a guaranteed worst case.
+        if limit == 0 {
+            heap_sort(&mut a[lo..=hi], compare);
+            return;
+        }
+        limit -= 1;
+
+        // Choice of pivot.
+        pivot(a, lo, hi, compare);
+
+        // Partition array and get the pivot indices.
+        let (left, right) = partition(a, lo, hi, compare);
+
+        // Sort the smaller partition, then carry on with the larger one.
+        if left - lo < hi - right {
+            // Left side of pivot.
+            if left > lo {
+                intro_sort(a, lo, left - 1, threshold, limit, compare);
+            }
+            lo = right + 1;
+        } else {
+            // Right side of pivot.
+            intro_sort(a, right + 1, hi, threshold, limit, compare);
+
+            if left == lo {
+                return;
+            }
+            hi = left - 1;
+        }
+    }
+}
+
+/// Sorts a (portion of an) array, divides it into three partitions around two
+/// pivots, then sorts those.
+///
+/// Only the two smaller partitions are sorted recursively, the largest one is
+/// sorted by the next iteration of the loop.
+fn dual_pivot_quick_sort<T, F>(a: &mut [T], mut lo: usize, mut hi: usize, compare: &mut F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    // Ensure indices are in correct order.
+    while lo < hi {
+        // Small partitions are cheaper to insertion sort.
+        if hi - lo < INSERTION_THRESHOLD {
+            insertion_sort(&mut a[lo..=hi], compare);
+            return;
+        }
+
+        // Choice of pivots.
+        dual_pivot(a, lo, hi, compare);
+
+        // Partition array and get the pivot indices.
+        let (left, right) = dual_partition(a, lo, hi, compare);
+
+        // [lo, left - 1] < p1, [left + 1, right - 1] between, [right + 1, hi] > p2.
+        let mut parts = [(lo, left), (left + 1, right), (right + 1, hi + 1)];
+
+        // If the pivots are equal, everything in between equals them too.
+        if compare(&a[left], &a[right]) == Ordering::Equal {
+            parts[1] = (left, left);
+        }
+
+        // Sort the two smaller partitions, then carry on with the largest one.
+        parts.sort_by_key(|&(begin, end)| end - begin);
+
+        for &(begin, end) in &parts[..2] {
+            if end > begin {
+                dual_pivot_quick_sort(a, begin, end - 1, compare);
+            }
+        }
+
+        let (begin, end) = parts[2];
+
+        if end == begin {
+            return;
+        }
+
+        lo = begin;
+        hi = end - 1;
+    }
+}
+
+/// Tertiles, moves the smaller pivot to `a[lo]` and the larger one to `a[hi]`.
+fn dual_pivot<T, F>(a: &mut [T], lo: usize, hi: usize, compare: &mut F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let third = (hi - lo) / 3;
+
+    a.swap(lo, lo + third);
+    a.swap(hi, hi - third);
+
+    if compare(&a[hi], &a[lo]) == Ordering::Less {
+        a.swap(lo, hi);
+    }
+}
+
+/// Divides array into three partitions around `p1 = a[lo]` and `p2 = a[hi]`.
+fn dual_partition<T, F>(a: &mut [T], lo: usize, hi: usize, compare: &mut F) -> (usize, usize)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    // Temporary pivot indices.
+    let mut l = lo + 1;
+    let mut k = lo + 1;
+    let mut g = hi - 1;
+
+    // [lo + 1, l) < p1;
+    // [l, k) >= p1 && <= p2;
+    // [k, g] not yet sorted;
+    // (g, hi - 1] > p2;
+    while k <= g {
+        if compare(&a[k], &a[lo]) == Ordering::Less {
+            a.swap(k, l);
+            l += 1;
+        } else if compare(&a[k], &a[hi]) == Ordering::Greater {
+            while k < g && compare(&a[g], &a[hi]) == Ordering::Greater {
+                g -= 1;
+            }
+
+            a.swap(k, g);
+            g -= 1;
+
+            if compare(&a[k], &a[lo]) == Ordering::Less {
+                a.swap(k, l);
+                l += 1;
+            }
+        }
+
+        k += 1;
+    }
+
+    // Move the pivots to their final positions.
+    l -= 1;
+    g += 1;
+    a.swap(lo, l);
+    a.swap(hi, g);
+
+    // The pivot indices.
+    (l, g)
+}
+
+/// Like [`quick_sort`] over the whole of `a[]`, but sorts the partitions
+/// below and above the pivot on two threads.
+fn par_quick_sort<T, F>(a: &mut [T], depth: u32, compare: &F)
+where
+    T: Send,
+    F: Fn(&T, &T) -> Ordering + Sync,
+{
+    let size = a.len();
+
+    if size <= 1 {
+        return;
+    }
+
+    let mut compare_mut = |x: &T, y: &T| compare(x, y);
+
+    if depth == 0 || size <= PAR_THRESHOLD {
+        let threshold = SortOptions::default().insertion_threshold;
+        let mut pivoter = Pivoter::new(PivotStrategy::MedianOfThree);
+        quick_sort(a, 0, size - 1, threshold, &mut pivoter, &mut compare_mut);
         return;
     }
 
     // Choice of pivot.
-    pivot(a, lo, hi);
+    pivot(a, 0, size - 1, &mut compare_mut);
 
     // Partition array and get the pivot indices.
-    let (left, right) = partition(a, lo, hi);
+    let (left, right) = partition(a, 0, size - 1, &mut compare_mut);
+
+    // Everything in [left, right] equals the pivot and is already in place.
+    let (less, rest) = a.split_at_mut(left);
+    let greater = &mut rest[right + 1 - left..];
 
     // Sort the two partitions.
-    if left > 1 {
-        // Left side of pivot.
-        quick_sort(a, lo, left - 1);
+    thread::scope(|s| {
+        s.spawn(|| par_quick_sort(less, depth - 1, compare));
+        par_quick_sort(greater, depth - 1, compare);
+    });
+}
+
+/// Partitions a (portion of an) array until `a[k]` is in its sorted position,
+/// narrowing down on the partition that contains `k`.
+///
+/// Pivots are chosen by median-of-three, unless `mom` is set or too many rounds
+/// went badly, then by median-of-medians.
+fn select<T, F>(a: &mut [T], mut lo: usize, mut hi: usize, k: usize, mut mom: bool, compare: &mut F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let mut bad_rounds = 0;
+
+    while lo < hi {
+        let size = hi - lo + 1;
+
+        // Small partitions are cheaper to insertion sort.
+        if size <= INSERTION_THRESHOLD {
+            insertion_sort(&mut a[lo..=hi], compare);
+            return;
+        }
+
+        // Choice of pivot.
+        if mom {
+            median_of_medians(a, lo, hi, compare);
+        } else {
+            pivot(a, lo, hi, compare);
+        }
+
+        // Partition array and get the pivot indices.
+        let (left, right) = partition(a, lo, hi, compare);
+
+        // Continue in the partition that contains `k`.
+        if k < left {
+            hi = left - 1;
+        } else if k > right {
+            lo = right + 1;
+        } else {
+            return;
+        }
+
+        // Too many bad pivots, switch to pivots with a guaranteed split.
+        if (hi + 1 - lo) * 4 > size * 3 {
+            bad_rounds += 1;
+            mom = mom || bad_rounds > SELECT_BAD_ROUNDS;
+        }
     }
-    // Right side of pivot.
-    quick_sort(a, right + 1, hi);
 }
 
-/// Median-of-three.
-fn pivot<T: Ord>(a: &mut [T], lo: usize, hi: usize) {
+/// Median-of-medians, moves a pivot to `a[hi]` that has at least 30% of the
+/// elements of `a[lo, hi]` on either side.
+fn median_of_medians<T, F>(a: &mut [T], lo: usize, hi: usize, compare: &mut F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let size = hi - lo + 1;
+    let groups = size / 5;
+
+    if groups == 0 {
+        insertion_sort(&mut a[lo..=hi], compare);
+        a.swap(lo + (size - 1) / 2, hi);
+        return;
+    }
+
+    // Move the median of each group of five to the front.
+    for group in 0..groups {
+        let start = lo + group * 5;
+        insertion_sort(&mut a[start..start + 5], &mut *compare);
+        a.swap(lo + group, start + 2);
+    }
+
+    // Select the median of the medians, again with median-of-medians pivots.
+    let mid = lo + (groups - 1) / 2;
+    select(a, lo, lo + groups - 1, mid, true, compare);
+
+    a.swap(mid, hi);
+}
+
+/// Chooses pivots according to a [`PivotStrategy`].
+struct Pivoter {
+    strategy: PivotStrategy,
+    // State of the random generator.
+    state: u64,
+}
+
+impl Pivoter {
+    fn new(strategy: PivotStrategy) -> Self {
+        let state = match strategy {
+            PivotStrategy::Random(seed) => seed,
+            _ => 0,
+        };
+
+        Pivoter { strategy, state }
+    }
+
+    /// Moves the chosen pivot of `a[lo, hi]` to `a[hi]`.
+    fn choose<T, F>(&mut self, a: &mut [T], lo: usize, hi: usize, compare: &mut F)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        let size = hi - lo + 1;
+        let mid = lo + (hi - lo) / 2;
+
+        let index = match self.strategy {
+            PivotStrategy::First => lo,
+            PivotStrategy::Last => hi,
+            PivotStrategy::MedianOfThree => median_of_three(a, lo, mid, hi, compare),
+            PivotStrategy::Ninther if size >= NINTHER_THRESHOLD => {
+                let eighth = size / 8;
+                ninther(a, lo + eighth, mid, hi - eighth, compare)
+            }
+            PivotStrategy::Ninther => median_of_three(a, lo, mid, hi, compare),
+            PivotStrategy::Random(_) => lo + (self.next() % size as u64) as usize,
+            PivotStrategy::MedianOfMedians => {
+                median_of_medians(a, lo, hi, compare);
+                hi
+            }
+        };
+
+        a.swap(index, hi);
+    }
+
+    /// SplitMix64, from <https://prng.di.unimi.it/splitmix64.c>.
+    fn next(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+/// Median-of-three, moves the median of `a[lo]`, `a[mid]` and `a[hi]` to `a[hi]`.
+fn pivot<T, F>(a: &mut [T], lo: usize, hi: usize, compare: &mut F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
     let mid = lo + (hi - lo) / 2;
+    let median = median_of_three(a, lo, mid, hi, compare);
+    a.swap(median, hi);
+}
 
-    if a[mid] < a[lo] {
-        a.swap(lo, mid);
+/// Returns the index of the median of `a[i]`, `a[j]` and `a[k]`.
+///
+/// `compare` returns [`Ordering::Less`] once for every pair that was out of order.
+pub(crate) fn median_of_three<T, F>(
+    a: &[T],
+    mut i: usize,
+    mut j: usize,
+    mut k: usize,
+    compare: &mut F,
+) -> usize
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    if compare(&a[j], &a[i]) == Ordering::Less {
+        std::mem::swap(&mut i, &mut j);
     }
 
-    if a[hi] < a[lo] {
-        a.swap(lo, hi);
+    if compare(&a[k], &a[j]) == Ordering::Less {
+        std::mem::swap(&mut j, &mut k);
     }
 
-    if a[mid] < a[hi] {
-        a.swap(mid, hi);
+    if compare(&a[j], &a[i]) == Ordering::Less {
+        std::mem::swap(&mut i, &mut j);
     }
+
+    j
+}
+
+/// Tukey's ninther, returns the index of the median of the medians of the
+/// neighbourhoods of `a[i]`, `a[j]` and `a[k]`.
+pub(crate) fn ninther<T, F>(a: &[T], i: usize, j: usize, k: usize, compare: &mut F) -> usize
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let i = median_of_three(a, i - 1, i, i + 1, compare);
+    let j = median_of_three(a, j - 1, j, j + 1, compare);
+    let k = median_of_three(a, k - 1, k, k + 1, compare);
+    median_of_three(a, i, j, k, compare)
 }
 
 /// Divides array into three partitions.
-fn partition<T: Ord>(a: &mut [T], lo: usize, hi: usize) -> (usize, usize) {
+fn partition<T, F>(a: &mut [T], lo: usize, hi: usize, compare: &mut F) -> (usize, usize)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
     // Choose the last element as the pivot.
     let p = hi;
 
@@ -80,7 +737,7 @@ fn partition<T: Ord>(a: &mut [T], lo: usize, hi: usize) -> (usize, usize) {
     // [j, k] not yet sorted;
     // [k + 1, hi - 1] > mid;
     while j <= k {
-        match a[j].cmp(&a[p]) {
+        match compare(&a[j], &a[p]) {
             Ordering::Less => {
                 a.swap(i, j);
                 i += 1;
@@ -190,6 +847,644 @@ mod tests {
         check_orderly(sort(&mut [5, 4, 3, 2, 1, 5, 4, 3, 2, 1, 5, 4, 3, 2, 1]));
     }
 
+    /// Plain median-of-three quick sort, with no guard against bad pivots, for
+    /// the adversary to play against.
+    fn reference_sort_by<T, F>(a: &mut [T], mut compare: F)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        if a.len() > 1 {
+            let hi = a.len() - 1;
+            let mut pivoter = Pivoter::new(PivotStrategy::MedianOfThree);
+            quick_sort(a, 0, hi, INSERTION_THRESHOLD, &mut pivoter, &mut compare);
+        }
+    }
+
+    /// Builds an input that drives [`reference_sort_by`] quadratic, using
+    /// McIlroy's adversary from "A Killer Adversary for Quicksort".
+    fn killer_input(size: usize) -> Vec<usize> {
+        killer_input_for(size, |a, compare| reference_sort_by(a, compare))
+    }
+
+    /// Builds an input that picks the worst pivot for `sort` every time it can.
+    fn killer_input_for<S>(size: usize, mut sort: S) -> Vec<usize>
+    where
+        S: FnMut(&mut [usize], &mut dyn FnMut(&usize, &usize) -> Ordering),
+    {
+        let gas = size;
+        let mut val = vec![gas; size];
+        let mut solid = 0;
+        let mut candidate = 0;
+
+        let mut indices = (0..size).collect::<Vec<usize>>();
+        sort(&mut indices, &mut |&x, &y| {
+            if val[x] == gas && val[y] == gas {
+                if x == candidate {
+                    val[x] = solid;
+                } else {
+                    val[y] = solid;
+                }
+                solid += 1;
+            }
+
+            if val[x] == gas {
+                candidate = x;
+            } else if val[y] == gas {
+                candidate = y;
+            }
+
+            val[x].cmp(&val[y])
+        });
+
+        val
+    }
+
+    #[test]
+    fn test_introsort_batch() {
+        check_orderly(introsort::<i32>(&mut []));
+        check_orderly(introsort(&mut [5]));
+        check_orderly(introsort(&mut [3, 2]));
+        check_orderly(introsort(&mut [2, 3]));
+        check_orderly(introsort(&mut [5, 1, 2]));
+        check_orderly(introsort(&mut [1, 100, 2, 3]));
+        check_orderly(introsort(&mut [1, 3, 5, 7, 9, 2, 4, 6, 8, 0]));
+        check_orderly(introsort(&mut [2, 4, 6, 2, 1, 8, 10, 3, 5, 7, 0, 9, 1]));
+        check_orderly(introsort(&mut [
+            9, 11, 9, 9, 9, 9, 11, 2, 3, 4, 11, 9, 0, 0, 0, 0,
+        ]));
+        check_orderly(introsort(&mut [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10]));
+        check_orderly(introsort(&mut [10, 9, 8, 7, 6, 5, 4, 3, 2, 1, 0]));
+        check_orderly(introsort(&mut [
+            0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 0, 0, 0, 1, 2,
+        ]));
+        check_orderly(introsort(&mut [
+            5, 4, 3, 2, 1, 5, 4, 3, 2, 1, 5, 4, 3, 2, 1,
+        ]));
+    }
+
+    #[test]
+    fn test_introsort_random() {
+        let mut rng = rand::thread_rng();
+        let range = Uniform::new_inclusive(-50000, 50000);
+        let mut data = (&mut rng)
+            .sample_iter(range)
+            .take(50000)
+            .collect::<Vec<i32>>();
+        introsort(&mut data);
+        check_orderly(&data);
+    }
+
+    #[test]
+    fn test_heap_sort_fallback() {
+        let mut rng = rand::thread_rng();
+        let range = Uniform::new_inclusive(-100, 100);
+        let mut data = (&mut rng)
+            .sample_iter(range)
+            .take(1000)
+            .collect::<Vec<i32>>();
+        let size = data.len();
+        intro_sort(
+            &mut data,
+            0,
+            size - 1,
+            INSERTION_THRESHOLD,
+            0,
+            &mut i32::cmp,
+        );
+        check_orderly(&data);
+    }
+
+    #[test]
+    fn test_introsort_killer() {
+        let size = 2000;
+        let killer = killer_input(size);
+
+        // The input is adversarial: the unguarded sort goes quadratic on it.
+        let mut data = killer.clone();
+        let mut count = 0;
+        reference_sort_by(&mut data, |x, y| {
+            count += 1;
+            x.cmp(y)
+        });
+        check_orderly(&data);
+        assert!(count > size * size / 8);
+
+        // Introsort, and the default sort built on it, stay within
+        // `c * n * log2(n)` comparisons.
+        let mut data = killer.clone();
+        let mut count = 0;
+        introsort_by(&mut data, |x, y| {
+            count += 1;
+            x.cmp(y)
+        });
+        check_orderly(&data);
+        assert!(count < 4 * size * 11);
+
+        let mut data = killer;
+        let mut count = 0;
+        sort_by(&mut data, |x, y| {
+            count += 1;
+            x.cmp(y)
+        });
+        check_orderly(&data);
+        assert!(count < 4 * size * 11);
+    }
+
+    /// Runs `f` on a thread with a deliberately small stack.
+    fn with_small_stack<F: FnOnce() + Send + 'static>(f: F) {
+        std::thread::Builder::new()
+            .stack_size(32 * 1024)
+            .spawn(f)
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+
+    #[test]
+    fn test_small_stack_killer() {
+        // Against the sort itself, the adversary gets a bad pivot at every level
+        // until the heap sort fallback.
+        let data = killer_input_for(300000, |a, compare| {
+            sort_by(a, compare);
+        });
+
+        with_small_stack(move || {
+            let mut sorted = data.clone();
+            sort(&mut sorted);
+            check_orderly(&sorted);
+
+            let mut sorted = data;
+            introsort(&mut sorted);
+            check_orderly(&sorted);
+        });
+    }
+
+    #[test]
+    fn test_small_stack_sorted_and_equal() {
+        let size = 300000;
+
+        with_small_stack(move || {
+            for data in [
+                (0..size).collect::<Vec<i32>>(),
+                (0..size).rev().collect::<Vec<i32>>(),
+                vec![7; size as usize],
+            ] {
+                let mut sorted = data.clone();
+                sort(&mut sorted);
+                check_orderly(&sorted);
+
+                let mut sorted = data;
+                introsort(&mut sorted);
+                check_orderly(&sorted);
+            }
+        });
+    }
+
+    #[test]
+    fn test_dual_pivot_batch() {
+        check_orderly(sort_dual_pivot::<i32>(&mut []));
+        check_orderly(sort_dual_pivot(&mut [5]));
+        check_orderly(sort_dual_pivot(&mut [3, 2]));
+        check_orderly(sort_dual_pivot(&mut [2, 3]));
+        check_orderly(sort_dual_pivot(&mut [5, 1, 2]));
+        check_orderly(sort_dual_pivot(&mut [1, 100, 2, 3]));
+        check_orderly(sort_dual_pivot(&mut [1, 3, 5, 7, 9, 2, 4, 6, 8, 0]));
+        check_orderly(sort_dual_pivot(&mut [
+            2, 4, 6, 2, 1, 8, 10, 3, 5, 7, 0, 9, 1,
+        ]));
+        check_orderly(sort_dual_pivot(&mut [
+            9, 11, 9, 9, 9, 9, 11, 2, 3, 4, 11, 9, 0, 0, 0, 0,
+        ]));
+        check_orderly(sort_dual_pivot(&mut [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10]));
+        check_orderly(sort_dual_pivot(&mut [10, 9, 8, 7, 6, 5, 4, 3, 2, 1, 0]));
+        check_orderly(sort_dual_pivot(&mut [
+            0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 0, 0, 0, 1, 2,
+        ]));
+        check_orderly(sort_dual_pivot(&mut [
+            5, 4, 3, 2, 1, 5, 4, 3, 2, 1, 5, 4, 3, 2, 1,
+        ]));
+    }
+
+    #[test]
+    fn test_dual_pivot_normal() {
+        let mut data = vec![2, 4, 6, 2, 1, 8, 10, 3, 5, 7, 0, 9, 1, 12, 6, 3, 11, 0, 4];
+        let mut sorted = data.clone();
+        sorted.sort();
+        sort_dual_pivot(&mut data);
+        assert_eq!(data, sorted);
+    }
+
+    #[test]
+    fn test_dual_pivot_patterns() {
+        let size = 50000;
+
+        let mut data = (0..size).collect::<Vec<i32>>();
+        sort_dual_pivot(&mut data);
+        check_orderly(&data);
+
+        let mut data = (0..size).rev().collect::<Vec<i32>>();
+        sort_dual_pivot(&mut data);
+        check_orderly(&data);
+
+        let mut data = (0..size).map(|x| x % 100).collect::<Vec<i32>>();
+        sort_dual_pivot(&mut data);
+        check_orderly(&data);
+
+        let mut data = vec![3; size as usize];
+        sort_dual_pivot(&mut data);
+        check_orderly(&data);
+    }
+
+    #[test]
+    fn test_dual_pivot_random() {
+        let mut rng = rand::thread_rng();
+
+        for max in [1, 10, 50000] {
+            let range = Uniform::new_inclusive(-max, max);
+            let mut data = (&mut rng)
+                .sample_iter(range)
+                .take(50000)
+                .collect::<Vec<i32>>();
+            sort_dual_pivot(&mut data);
+            check_orderly(&data);
+        }
+    }
+
+    #[test]
+    fn test_dual_pivot_by() {
+        let mut data = vec![
+            -10, 1, 2, 3, 3, -20, 5, 43, 0, 7, -8, 12, 9, -1, 6, 4, 11, 2,
+        ];
+        sort_dual_pivot_by(&mut data, |x, y| y.cmp(x));
+        data.reverse();
+        check_orderly(&data);
+    }
+
+    #[test]
+    fn test_par_batch() {
+        check_orderly(par_sort::<i32>(&mut []));
+        check_orderly(par_sort(&mut [5]));
+        check_orderly(par_sort(&mut [3, 2]));
+        check_orderly(par_sort(&mut [2, 3]));
+        check_orderly(par_sort(&mut [5, 1, 2]));
+        check_orderly(par_sort(&mut [1, 100, 2, 3]));
+        check_orderly(par_sort(&mut [1, 3, 5, 7, 9, 2, 4, 6, 8, 0]));
+        check_orderly(par_sort(&mut [2, 4, 6, 2, 1, 8, 10, 3, 5, 7, 0, 9, 1]));
+        check_orderly(par_sort(&mut [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 0, 0, 0, 1, 2]));
+        check_orderly(par_sort(&mut [5, 4, 3, 2, 1, 5, 4, 3, 2, 1, 5, 4, 3, 2, 1]));
+    }
+
+    #[test]
+    fn test_par_random_large() {
+        let mut rng = rand::thread_rng();
+        let range = Uniform::new_inclusive(-500000, 500000);
+        let mut data = (&mut rng)
+            .sample_iter(range)
+            .take(200000)
+            .collect::<Vec<i32>>();
+        par_sort(&mut data);
+        check_orderly(&data);
+    }
+
+    #[test]
+    fn test_par_duplicates() {
+        let mut rng = rand::thread_rng();
+        let range = Uniform::new_inclusive(0, 10);
+        let mut data = (&mut rng)
+            .sample_iter(range)
+            .take(200000)
+            .collect::<Vec<i32>>();
+        let mut sorted = data.clone();
+        sorted.sort();
+        par_sort(&mut data);
+        assert_eq!(data, sorted);
+
+        let mut data = vec![7; 100000];
+        par_sort(&mut data);
+        assert_eq!(data, vec![7; 100000]);
+    }
+
+    #[test]
+    fn test_par_sort_by() {
+        let mut rng = rand::thread_rng();
+        let range = Uniform::new_inclusive(0, 1000000);
+        let mut data = (&mut rng)
+            .sample_iter(range)
+            .take(50000)
+            .map(|x: i32| x.to_string())
+            .collect::<Vec<String>>();
+        par_sort_by(&mut data, |x, y| y.cmp(x));
+        data.reverse();
+        check_orderly(&data);
+    }
+
+    fn check_selected<T: Ord>(a: &[T], k: usize) {
+        assert!(a[..k].iter().all(|x| *x <= a[k]));
+        assert!(a[k + 1..].iter().all(|x| *x >= a[k]));
+    }
+
+    #[test]
+    fn test_select_nth() {
+        let data = vec![2, 4, 6, 2, 1, 8, 10, 3, 5, 7, 0, 9, 1];
+        let sorted = vec![0, 1, 1, 2, 2, 3, 4, 5, 6, 7, 8, 9, 10];
+
+        for (k, expected) in sorted.iter().enumerate() {
+            let mut selected = data.clone();
+            assert_eq!(select_nth(&mut selected, k), expected);
+            check_selected(&selected, k);
+        }
+    }
+
+    #[test]
+    fn test_select_nth_random() {
+        let mut rng = rand::thread_rng();
+
+        for max in [10, 50000] {
+            let range = Uniform::new_inclusive(0, max);
+            let data = (&mut rng)
+                .sample_iter(range)
+                .take(5000)
+                .collect::<Vec<i32>>();
+            let mut sorted = data.clone();
+            sorted.sort();
+
+            for k in [0, 1, 17, 2500, 4950, 4999] {
+                let mut selected = data.clone();
+                assert_eq!(*select_nth(&mut selected, k), sorted[k]);
+                check_selected(&selected, k);
+            }
+        }
+    }
+
+    #[test]
+    fn test_select_nth_by() {
+        let mut data = vec![(1, 'b'), (3, 'a'), (2, 'c'), (0, 'a'), (5, 'd')];
+        let median = select_nth_by(&mut data, 2, |x, y| x.1.cmp(&y.1));
+        assert_eq!(median.1, 'b');
+    }
+
+    #[test]
+    fn test_select_nth_median_of_medians() {
+        let mut rng = rand::thread_rng();
+        let range = Uniform::new_inclusive(-100, 100);
+        let data = (&mut rng)
+            .sample_iter(range)
+            .take(5000)
+            .collect::<Vec<i32>>();
+        let mut sorted = data.clone();
+        sorted.sort();
+
+        for k in [0, 1000, 2500, 4999] {
+            let mut selected = data.clone();
+            let size = selected.len();
+            select(&mut selected, 0, size - 1, k, true, &mut i32::cmp);
+            assert_eq!(selected[k], sorted[k]);
+            check_selected(&selected, k);
+        }
+    }
+
+    #[test]
+    fn test_select_nth_killer() {
+        let size = 4000;
+        let killer = killer_input(size);
+
+        for k in [0, size / 2, size - 1] {
+            let mut data = killer.clone();
+            let mut count = 0;
+            select_nth_by(&mut data, k, |x, y| {
+                count += 1;
+                x.cmp(y)
+            });
+            check_selected(&data, k);
+            assert!(count < 40 * size);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_select_nth_out_of_range() {
+        select_nth(&mut [1, 2, 3], 3);
+    }
+
+    fn check_partitioned<T: Ord>(
+        a: &[T],
+        pivot: &T,
+        (less, equal, greater): (Range<usize>, Range<usize>, Range<usize>),
+    ) {
+        assert_eq!(less.start, 0);
+        assert_eq!(less.end, equal.start);
+        assert_eq!(equal.end, greater.start);
+        assert_eq!(greater.end, a.len());
+        assert!(a[less].iter().all(|x| x < pivot));
+        assert!(a[equal].iter().all(|x| x == pivot));
+        assert!(a[greater].iter().all(|x| x > pivot));
+    }
+
+    #[test]
+    fn test_partition_three_way() {
+        let mut data = vec![2, 4, 6, 2, 1, 8, 10, 3, 5, 7, 0, 9, 1];
+        let ranges = partition_three_way(&mut data, &2);
+        assert_eq!(ranges, (0..3, 3..5, 5..13));
+        check_partitioned(&data, &2, ranges);
+
+        let mut data = vec![9, 11, 9, 9, 9, 9, 11, 2, 3, 4, 11, 9, 0, 0, 0, 0];
+        let ranges = partition_three_way(&mut data, &9);
+        check_partitioned(&data, &9, ranges);
+    }
+
+    #[test]
+    fn test_partition_all_equal() {
+        let mut data = vec![7; 10];
+        let ranges = partition_three_way(&mut data, &7);
+        assert_eq!(ranges, (0..0, 0..10, 10..10));
+        check_partitioned(&data, &7, ranges);
+    }
+
+    #[test]
+    fn test_partition_none_equal() {
+        let mut data = vec![5, 4, 3, 2, 1, 10, 9, 8, 7, 6];
+        let ranges = partition_three_way(&mut data, &0);
+        assert_eq!(ranges, (0..0, 0..0, 0..10));
+        check_partitioned(&data, &0, ranges);
+
+        let ranges = partition_three_way(&mut data, &11);
+        assert_eq!(ranges, (0..10, 10..10, 10..10));
+        check_partitioned(&data, &11, ranges);
+
+        let ranges = partition_three_way(&mut data, &5);
+        assert_eq!(ranges, (0..4, 4..5, 5..10));
+        check_partitioned(&data, &5, ranges);
+
+        let mut data = vec![1, 3, 5, 7, 9, 2, 4, 6, 8, 0];
+        let ranges = partition_three_way(&mut data, &-1);
+        check_partitioned(&data, &-1, ranges);
+    }
+
+    #[test]
+    fn test_partition_empty() {
+        let mut data: Vec<i32> = vec![];
+        assert_eq!(partition_three_way(&mut data, &0), (0..0, 0..0, 0..0));
+    }
+
+    #[test]
+    fn test_partition_by() {
+        let mut data = vec![
+            (10, "a"),
+            (25, "b"),
+            (5, "c"),
+            (20, "d"),
+            (30, "e"),
+            (20, "f"),
+        ];
+        let (below, at, above) = partition_by(&mut data, |x| x.0.cmp(&20));
+        assert!(data[below].iter().all(|x| x.0 < 20));
+        assert_eq!(at.len(), 2);
+        assert!(data[at].iter().all(|x| x.0 == 20));
+        assert!(data[above].iter().all(|x| x.0 > 20));
+    }
+
+    #[test]
+    fn test_partition_random() {
+        let mut rng = rand::thread_rng();
+        let range = Uniform::new_inclusive(0, 20);
+        let mut data = (&mut rng)
+            .sample_iter(range)
+            .take(1000)
+            .collect::<Vec<i32>>();
+
+        for pivot in [-1, 0, 10, 20, 21] {
+            let ranges = partition_three_way(&mut data, &pivot);
+            check_partitioned(&data, &pivot, ranges);
+        }
+    }
+
+    const STRATEGIES: [PivotStrategy; 6] = [
+        PivotStrategy::First,
+        PivotStrategy::Last,
+        PivotStrategy::MedianOfThree,
+        PivotStrategy::Ninther,
+        PivotStrategy::Random(42),
+        PivotStrategy::MedianOfMedians,
+    ];
+
+    #[test]
+    fn test_sort_with_batch() {
+        for strategy in STRATEGIES {
+            check_orderly(sort_with::<i32>(&mut [], strategy));
+            check_orderly(sort_with(&mut [5], strategy));
+            check_orderly(sort_with(&mut [3, 2], strategy));
+            check_orderly(sort_with(&mut [2, 3], strategy));
+            check_orderly(sort_with(&mut [5, 1, 2], strategy));
+            check_orderly(sort_with(&mut [1, 100, 2, 3], strategy));
+            check_orderly(sort_with(&mut [1, 3, 5, 7, 9, 2, 4, 6, 8, 0], strategy));
+            check_orderly(sort_with(
+                &mut [2, 4, 6, 2, 1, 8, 10, 3, 5, 7, 0, 9, 1],
+                strategy,
+            ));
+            check_orderly(sort_with(
+                &mut [9, 11, 9, 9, 9, 9, 11, 2, 3, 4, 11, 9, 0, 0, 0, 0],
+                strategy,
+            ));
+            check_orderly(sort_with(&mut [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10], strategy));
+            check_orderly(sort_with(&mut [10, 9, 8, 7, 6, 5, 4, 3, 2, 1, 0], strategy));
+            check_orderly(sort_with(
+                &mut [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 0, 0, 0, 1, 2],
+                strategy,
+            ));
+            check_orderly(sort_with(
+                &mut [5, 4, 3, 2, 1, 5, 4, 3, 2, 1, 5, 4, 3, 2, 1],
+                strategy,
+            ));
+        }
+    }
+
+    #[test]
+    fn test_sort_with_random() {
+        let mut rng = rand::thread_rng();
+        let range = Uniform::new_inclusive(-500, 500);
+        let data = (&mut rng)
+            .sample_iter(range)
+            .take(10000)
+            .collect::<Vec<i32>>();
+
+        for strategy in STRATEGIES {
+            let mut sorted = data.clone();
+            sort_with(&mut sorted, strategy);
+            check_orderly(&sorted);
+
+            sort_with_by(&mut sorted, strategy, |x, y| y.cmp(x));
+            sorted.reverse();
+            check_orderly(&sorted);
+        }
+    }
+
+    #[test]
+    fn test_sort_with_seeded() {
+        let mut rng = rand::thread_rng();
+        let range = Uniform::new_inclusive(0, 1000000);
+        let data = (&mut rng)
+            .sample_iter(range)
+            .take(5000)
+            .collect::<Vec<i32>>();
+
+        let count_comparisons = |seed| {
+            let mut count = 0;
+            sort_with_by(&mut data.clone(), PivotStrategy::Random(seed), |x, y| {
+                count += 1;
+                x.cmp(y)
+            });
+            count
+        };
+
+        // The same seed makes the same choices.
+        assert_eq!(count_comparisons(7), count_comparisons(7));
+    }
+
+    #[test]
+    fn test_sort_with_killer() {
+        let size = 2000;
+        let killer = killer_input(size);
+
+        for strategy in [
+            PivotStrategy::Ninther,
+            PivotStrategy::Random(1),
+            PivotStrategy::MedianOfMedians,
+        ] {
+            let mut data = killer.clone();
+            let mut count = 0;
+            sort_with_by(&mut data, strategy, |x, y| {
+                count += 1;
+                x.cmp(y)
+            });
+            check_orderly(&data);
+            assert!(count < size * size / 8);
+        }
+    }
+
+    #[test]
+    fn test_sort_with_options() {
+        let mut rng = rand::thread_rng();
+        let range = Uniform::new_inclusive(-500, 500);
+        let data = (&mut rng)
+            .sample_iter(range)
+            .take(5000)
+            .collect::<Vec<i32>>();
+
+        for insertion_threshold in [0, 1, 2, 5, 16, 100, 10000] {
+            let options = SortOptions {
+                insertion_threshold,
+            };
+
+            let mut sorted = data.clone();
+            sort_with_options(&mut sorted, options);
+            check_orderly(&sorted);
+
+            sort_by_with_options(&mut sorted, options, |x, y| y.cmp(x));
+            sorted.reverse();
+            check_orderly(&sorted);
+        }
+    }
+
     #[test]
     fn test_random_small() {
         let mut rng = rand::thread_rng();
@@ -210,4 +1505,42 @@ mod tests {
         sort(&mut data);
         check_orderly(&data);
     }
+
+    #[test]
+    fn test_descending() {
+        let mut data = vec![2, 4, 6, 2, 1, 8, 10, 3, 5, 7, 0, 9, 1];
+        let sorted = vec![10, 9, 8, 7, 6, 5, 4, 3, 2, 2, 1, 1, 0];
+        sort_descending(&mut data);
+        assert_eq!(data, sorted);
+    }
+
+    #[test]
+    fn test_sort_by() {
+        let mut data = vec![(1, 'b'), (3, 'a'), (2, 'c'), (0, 'a')];
+        sort_by(&mut data, |x, y| x.1.cmp(&y.1).then(y.0.cmp(&x.0)));
+        assert_eq!(data, vec![(3, 'a'), (0, 'a'), (1, 'b'), (2, 'c')]);
+    }
+
+    #[test]
+    fn test_sort_by_key() {
+        let mut data = vec![-10, 1, 2, 3, 3, -20, 5, 43];
+        sort_by_key(&mut data, |x: &i32| x.abs());
+        assert_eq!(data, vec![1, 2, 3, 3, 5, -10, -20, 43]);
+
+        sort_by_key_descending(&mut data, |x: &i32| x.abs());
+        assert_eq!(data, vec![43, -20, -10, 5, 3, 3, 2, 1]);
+    }
+
+    #[test]
+    fn test_random_sort_by() {
+        let mut rng = rand::thread_rng();
+        let range = Uniform::new_inclusive(-50000, 50000);
+        let mut data = (&mut rng)
+            .sample_iter(range)
+            .take(50000)
+            .collect::<Vec<i32>>();
+        sort_by(&mut data, |x, y| y.cmp(x));
+        data.reverse();
+        check_orderly(&data);
+    }
 }
@@ -0,0 +1,435 @@
+//! # Radix Sort
+//!
+//! This is adapted from
+//!
+//! <https://en.wikipedia.org/wiki/Radix_sort>
+//!
+//! <http://stereopsis.com/radix.html>
+//!
+//! Test case is derived from
+//!
+//! <https://github.com/rust-lang/rust/blob/60bd3f96779dbe6bd206dae09395e9af7d580552/library/alloc/src/collections/binary_heap/tests.rs>
+
+use std::mem;
+use std::ptr;
+
+use crate::insertion_sort::insertion_sort;
+
+/// Number of buckets, one for every value of a byte.
+const RADIX: usize = 256;
+
+/// Buckets up to this length are insertion sorted by [`sort_msd`].
+const MSD_THRESHOLD: usize = 32;
+
+/// Values that can be sorted one byte at a time, through an unsigned key that
+/// orders the same way as the values themselves.
+pub trait RadixKey: Copy {
+    /// Number of significant bytes in the key.
+    const BYTES: usize;
+
+    /// Returns the key of the value, in its lowest [`Self::BYTES`] bytes.
+    fn radix_key(self) -> u64;
+}
+
+macro_rules! impl_radix_key_unsigned {
+    ($($t:ty),*) => {$(
+        impl RadixKey for $t {
+            const BYTES: usize = mem::size_of::<$t>();
+
+            #[inline]
+            fn radix_key(self) -> u64 {
+                self as u64
+            }
+        }
+    )*};
+}
+
+macro_rules! impl_radix_key_signed {
+    ($($t:ty => $u:ty),*) => {$(
+        impl RadixKey for $t {
+            const BYTES: usize = mem::size_of::<$t>();
+
+            /// Flips the sign bit, so negative values come first.
+            #[inline]
+            fn radix_key(self) -> u64 {
+                (self as $u ^ (1 << (<$u>::BITS - 1))) as u64
+            }
+        }
+    )*};
+}
+
+macro_rules! impl_radix_key_float {
+    ($($t:ty => $u:ty),*) => {$(
+        impl RadixKey for $t {
+            const BYTES: usize = mem::size_of::<$t>();
+
+            /// Flips all the bits of negative values and only the sign bit of
+            /// positive ones, which orders them like [`f64::total_cmp`].
+            #[inline]
+            fn radix_key(self) -> u64 {
+                let bits = self.to_bits();
+                let sign = 1 << (<$u>::BITS - 1);
+
+                if bits & sign != 0 {
+                    !bits as u64
+                } else {
+                    (bits ^ sign) as u64
+                }
+            }
+        }
+    )*};
+}
+
+impl_radix_key_unsigned!(u8, u16, u32, u64, usize);
+impl_radix_key_signed!(i8 => u8, i16 => u16, i32 => u32, i64 => u64, isize => usize);
+impl_radix_key_float!(f32 => u32, f64 => u64);
+
+/// Sorting the entire array, least significant byte first.
+///
+/// Floats are ordered like [`f64::total_cmp`].
+pub fn sort<T: RadixKey>(a: &mut [T]) -> &mut [T] {
+    lsd_sort(a, T::BYTES, |x| x.radix_key());
+    a
+}
+
+/// Sorting the entire array, most significant byte first, insertion sorting
+/// the small buckets.
+///
+/// Floats are ordered like [`f64::total_cmp`].
+pub fn sort_msd<T: RadixKey>(a: &mut [T]) -> &mut [T] {
+    let size = a.len();
+
+    if size <= 1 {
+        return a;
+    }
+
+    let mut b = a.to_vec();
+    msd_sort(a, &mut b, T::BYTES);
+    a
+}
+
+/// Sorting the entire array by the radix key that `f` extracts from each
+/// element, least significant byte first.
+///
+/// The sort is stable: elements with equal keys keep their original order,
+/// and `f` is called exactly once for every element.
+pub fn sort_by_key<T, K, F>(a: &mut [T], mut f: F) -> &mut [T]
+where
+    K: RadixKey,
+    F: FnMut(&T) -> K,
+{
+    let mut keyed = a
+        .iter()
+        .enumerate()
+        .map(|(i, x)| (f(x).radix_key(), i))
+        .collect::<Vec<(u64, usize)>>();
+
+    lsd_sort(&mut keyed, K::BYTES, |x| x.0);
+
+    let order = keyed.into_iter().map(|(_, i)| i).collect::<Vec<usize>>();
+    gather(a, &order);
+    a
+}
+
+/// Counting sorts `a[]` by each byte of `key`, from the least significant one
+/// up, which keeps the order of the previous passes for equal bytes.
+fn lsd_sort<T, K>(a: &mut [T], bytes: usize, key: K)
+where
+    T: Copy,
+    K: Fn(&T) -> u64,
+{
+    let size = a.len();
+
+    if size <= 1 {
+        return;
+    }
+
+    // Count the bytes of every pass at once.
+    let mut counts = vec![[0usize; RADIX]; bytes];
+
+    for x in a.iter() {
+        let k = key(x);
+
+        for (pass, count) in counts.iter_mut().enumerate() {
+            count[digit(k, pass)] += 1;
+        }
+    }
+
+    let mut b = a.to_vec();
+    let mut src_is_a = true;
+
+    for (pass, count) in counts.iter_mut().enumerate() {
+        // Every element has the same byte here, this pass would not move anything.
+        if count.contains(&size) {
+            continue;
+        }
+
+        // Turn the counts into the start of each bucket.
+        let mut start = 0;
+
+        for c in count.iter_mut() {
+            let n = *c;
+            *c = start;
+            start += n;
+        }
+
+        let (src, dst) = if src_is_a {
+            (&*a, &mut b[..])
+        } else {
+            (&b[..], &mut *a)
+        };
+
+        for x in src {
+            let d = digit(key(x), pass);
+            dst[count[d]] = *x;
+            count[d] += 1;
+        }
+
+        src_is_a = !src_is_a;
+    }
+
+    if !src_is_a {
+        a.copy_from_slice(&b);
+    }
+}
+
+/// Counting sorts `a[]` by byte `bytes - 1` of its keys, then sorts each
+/// bucket by the next byte down.
+///
+/// `b[]` is a work array as long as `a[]`.
+fn msd_sort<T: RadixKey>(a: &mut [T], b: &mut [T], bytes: usize) {
+    let size = a.len();
+
+    // Small buckets are cheaper to insertion sort.
+    if size <= MSD_THRESHOLD {
+        insertion_sort(a, &mut |x: &T, y: &T| x.radix_key().cmp(&y.radix_key()));
+        return;
+    }
+
+    let pass = bytes - 1;
+    let mut count = [0usize; RADIX];
+
+    for x in a.iter() {
+        count[digit(x.radix_key(), pass)] += 1;
+    }
+
+    // Turn the counts into the start of each bucket.
+    let mut starts = [0usize; RADIX + 1];
+
+    for d in 0..RADIX {
+        starts[d + 1] = starts[d] + count[d];
+    }
+
+    let mut next = starts;
+
+    for x in a.iter() {
+        let d = digit(x.radix_key(), pass);
+        b[next[d]] = *x;
+        next[d] += 1;
+    }
+
+    a.copy_from_slice(b);
+
+    if pass == 0 {
+        return;
+    }
+
+    // Sort each bucket by the next byte down.
+    for d in 0..RADIX {
+        let (start, end) = (starts[d], starts[d + 1]);
+
+        if end - start > 1 {
+            msd_sort(&mut a[start..end], &mut b[start..end], pass);
+        }
+    }
+}
+
+/// Returns byte `pass` of `key`, counting from the least significant one.
+#[inline]
+fn digit(key: u64, pass: usize) -> usize {
+    ((key >> (8 * pass)) & 0xff) as usize
+}
+
+/// Reorders `a[]` so that `a[i]` is the element that was at `a[order[i]]`.
+///
+/// `order` must be a permutation of the indices of `a[]`.
+pub(crate) fn gather<T>(a: &mut [T], order: &[usize]) {
+    let size = a.len();
+
+    assert_eq!(order.len(), size);
+
+    let mut b: Vec<T> = Vec::with_capacity(size);
+
+    // SAFETY: `order` is a permutation, so every element is moved out exactly
+    // once, then all of them are moved back. `b` never owns them, its length
+    // stays 0.
+    unsafe {
+        let src = a.as_mut_ptr();
+        let dst = b.as_mut_ptr();
+
+        for (i, &j) in order.iter().enumerate() {
+            ptr::copy_nonoverlapping(src.add(j), dst.add(i), 1);
+        }
+
+        ptr::copy_nonoverlapping(dst, src, size);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::distributions::Uniform;
+    use rand::Rng;
+
+    use super::*;
+
+    fn check_orderly<T: PartialOrd>(a: &[T]) {
+        if a.is_empty() {
+            return;
+        }
+
+        for i in 0..a.len() - 1 {
+            assert!(a[i] <= a[i + 1]);
+        }
+    }
+
+    #[test]
+    fn test_basic() {
+        let mut data = vec![5u32, 9, 3];
+        let sorted = vec![3, 5, 9];
+        sort(&mut data);
+        assert_eq!(data, sorted);
+
+        let mut data = vec![5u32, 9, 3];
+        sort_msd(&mut data);
+        assert_eq!(data, sorted);
+    }
+
+    #[test]
+    fn test_batch() {
+        for sorter in [sort::<i32>, sort_msd::<i32>] {
+            check_orderly(sorter(&mut []));
+            check_orderly(sorter(&mut [5]));
+            check_orderly(sorter(&mut [3, 2]));
+            check_orderly(sorter(&mut [2, 3]));
+            check_orderly(sorter(&mut [5, 1, 2]));
+            check_orderly(sorter(&mut [1, 100, 2, 3]));
+            check_orderly(sorter(&mut [1, 3, 5, 7, 9, 2, 4, 6, 8, 0]));
+            check_orderly(sorter(&mut [2, 4, 6, 2, 1, 8, 10, 3, 5, 7, 0, 9, 1]));
+            check_orderly(sorter(&mut [
+                9, 11, 9, 9, 9, 9, 11, 2, 3, 4, 11, 9, 0, 0, 0, 0,
+            ]));
+            check_orderly(sorter(&mut [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10]));
+            check_orderly(sorter(&mut [10, 9, 8, 7, 6, 5, 4, 3, 2, 1, 0]));
+            check_orderly(sorter(&mut [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 0, 0, 0, 1, 2]));
+            check_orderly(sorter(&mut [5, 4, 3, 2, 1, 5, 4, 3, 2, 1, 5, 4, 3, 2, 1]));
+        }
+    }
+
+    #[test]
+    fn test_negative() {
+        let mut data = vec![-10, 1, 2, 3, 3, -20, 5, 43, i64::MIN, i64::MAX, 0, -1];
+        let mut sorted = data.clone();
+        sorted.sort();
+
+        assert_eq!(sort(&mut data.clone()), sorted);
+        assert_eq!(sort_msd(&mut data), sorted);
+
+        let mut data = vec![-128i8, 127, 0, -1, 1, -5];
+        sort(&mut data);
+        assert_eq!(data, vec![-128, -5, -1, 0, 1, 127]);
+    }
+
+    #[test]
+    fn test_floats() {
+        let data = vec![
+            3.5,
+            -0.0,
+            0.0,
+            -2.25,
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+            1e-300,
+            -1e300,
+            f64::NAN,
+            -f64::NAN,
+            42.0,
+        ];
+        let mut sorted = data.clone();
+        sorted.sort_by(f64::total_cmp);
+        let bits = |a: &[f64]| a.iter().map(|x| x.to_bits()).collect::<Vec<u64>>();
+
+        assert_eq!(bits(sort(&mut data.clone())), bits(&sorted));
+        assert_eq!(bits(sort_msd(&mut data.clone())), bits(&sorted));
+
+        let mut data = data.iter().map(|&x| x as f32).collect::<Vec<f32>>();
+        sort(&mut data);
+        assert!(data[0].is_nan() && data[1] == f32::NEG_INFINITY);
+        check_orderly(&data[1..data.len() - 1]);
+    }
+
+    #[test]
+    fn test_random() {
+        let mut rng = rand::thread_rng();
+
+        let mut data = (&mut rng)
+            .sample_iter(Uniform::new_inclusive(0, u64::MAX))
+            .take(50000)
+            .collect::<Vec<u64>>();
+        let mut sorted = data.clone();
+        sorted.sort();
+        assert_eq!(sort(&mut data.clone()), sorted);
+        assert_eq!(sort_msd(&mut data), sorted);
+
+        let range = Uniform::new_inclusive(-50000, 50000);
+        let mut data = (&mut rng)
+            .sample_iter(range)
+            .take(50000)
+            .collect::<Vec<i32>>();
+        let mut sorted = data.clone();
+        sorted.sort();
+        assert_eq!(sort(&mut data.clone()), sorted);
+        assert_eq!(sort_msd(&mut data), sorted);
+
+        let range = Uniform::new(-1e9, 1e9);
+        let mut data = (&mut rng)
+            .sample_iter(range)
+            .take(50000)
+            .collect::<Vec<f64>>();
+        sort_msd(&mut data);
+        check_orderly(&data);
+    }
+
+    #[test]
+    fn test_sort_by_key_stable() {
+        let mut rng = rand::thread_rng();
+        let range = Uniform::new_inclusive(-100, 100);
+        let data = (&mut rng)
+            .sample_iter(range)
+            .take(10000)
+            .enumerate()
+            .map(|(i, x)| (x, format!("record {i}")))
+            .collect::<Vec<(i64, String)>>();
+
+        let mut sorted = data.clone();
+        sorted.sort_by_key(|x| x.0);
+
+        let mut data = data;
+        sort_by_key(&mut data, |x| x.0);
+        assert_eq!(data, sorted);
+    }
+
+    #[test]
+    fn test_sort_by_key_timestamps() {
+        let mut data = vec![
+            ("c", 1_700_000_002u32),
+            ("a", 1_700_000_000),
+            ("d", 1_700_000_002),
+            ("b", 1_600_000_000),
+        ];
+        sort_by_key(&mut data, |x| x.1);
+        assert_eq!(
+            data.iter().map(|x| x.0).collect::<Vec<&str>>(),
+            ["b", "a", "c", "d"]
+        );
+    }
+}
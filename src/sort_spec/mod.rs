@@ -0,0 +1,506 @@
+//! # Sort Specification
+//!
+//! This is adapted from
+//!
+//! <https://www.postgresql.org/docs/current/queries-order.html>
+//!
+//! <https://en.wikipedia.org/wiki/Order_by>
+//!
+//! Test case is derived from
+//!
+//! <https://github.com/rust-lang/rust/blob/60bd3f96779dbe6bd206dae09395e9af7d580552/library/alloc/src/collections/binary_heap/tests.rs>
+
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::merge_sort;
+use crate::pdqsort;
+
+/// The direction a column is sorted in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Ascending,
+    Descending,
+}
+
+/// Where missing values of a column go, whatever its direction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Nulls {
+    First,
+    Last,
+}
+
+impl Nulls {
+    /// The position used when a key does not give one: nulls sort as if they
+    /// were larger than any value, last when ascending and first when
+    /// descending, as in PostgreSQL.
+    pub fn default_for(direction: Direction) -> Self {
+        match direction {
+            Direction::Ascending => Nulls::Last,
+            Direction::Descending => Nulls::First,
+        }
+    }
+}
+
+/// One column of a [`SortSpec`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SortKey<C> {
+    pub column: C,
+    pub direction: Direction,
+    pub nulls: Nulls,
+}
+
+/// A list of columns to sort by, each one breaking the ties of the one before,
+/// like the `ORDER BY` clause of SQL.
+///
+/// The columns are named by any `C`, and the rows are compared through an
+/// accessor that looks up the value of a column in a row, returning `None` for
+/// a null.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SortSpec<C> {
+    keys: Vec<SortKey<C>>,
+}
+
+impl<C> Default for SortSpec<C> {
+    /// Creates an empty specification, under which all rows are equal.
+    fn default() -> Self {
+        SortSpec { keys: Vec::new() }
+    }
+}
+
+impl<C> SortSpec<C> {
+    /// Creates an empty specification.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a column with the default position of nulls for its direction.
+    pub fn then(self, column: C, direction: Direction) -> Self {
+        self.then_with_nulls(column, direction, Nulls::default_for(direction))
+    }
+
+    /// Appends a column with an explicit position of nulls.
+    pub fn then_with_nulls(mut self, column: C, direction: Direction, nulls: Nulls) -> Self {
+        self.keys.push(SortKey {
+            column,
+            direction,
+            nulls,
+        });
+        self
+    }
+
+    /// Appends an ascending column.
+    pub fn asc(self, column: C) -> Self {
+        self.then(column, Direction::Ascending)
+    }
+
+    /// Appends a descending column.
+    pub fn desc(self, column: C) -> Self {
+        self.then(column, Direction::Descending)
+    }
+
+    /// The columns, most significant first.
+    pub fn keys(&self) -> &[SortKey<C>] {
+        &self.keys
+    }
+
+    /// Compares two rows column by column, looking up the values with `get`.
+    pub fn compare<T, V, F>(&self, x: &T, y: &T, mut get: F) -> Ordering
+    where
+        V: Ord,
+        F: FnMut(&T, &C) -> Option<V>,
+    {
+        for key in &self.keys {
+            let ordering = match (get(x, &key.column), get(y, &key.column)) {
+                (Some(u), Some(v)) => match key.direction {
+                    Direction::Ascending => u.cmp(&v),
+                    Direction::Descending => v.cmp(&u),
+                },
+                (None, None) => Ordering::Equal,
+                (None, Some(_)) => match key.nulls {
+                    Nulls::First => Ordering::Less,
+                    Nulls::Last => Ordering::Greater,
+                },
+                (Some(_), None) => match key.nulls {
+                    Nulls::First => Ordering::Greater,
+                    Nulls::Last => Ordering::Less,
+                },
+            };
+
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+        }
+
+        Ordering::Equal
+    }
+
+    /// Turns the specification into a comparator function, which can be given
+    /// to any of the `sort_by` functions.
+    pub fn comparator<'a, T, V, F>(&'a self, mut get: F) -> impl FnMut(&T, &T) -> Ordering + 'a
+    where
+        V: Ord,
+        F: FnMut(&T, &C) -> Option<V> + 'a,
+    {
+        move |x, y| self.compare(x, y, &mut get)
+    }
+
+    /// Sorting the entire array by the specification, with [`merge_sort`].
+    ///
+    /// The sort is stable: equal elements keep their original order.
+    pub fn sort<'b, T, V, F>(&self, a: &'b mut [T], get: F) -> &'b mut [T]
+    where
+        V: Ord,
+        F: FnMut(&T, &C) -> Option<V>,
+    {
+        merge_sort::sort_by(a, self.comparator(get))
+    }
+
+    /// Sorting the entire array by the specification, with [`pdqsort`], so that
+    /// no input takes more than `O(n log n)` comparisons.
+    pub fn sort_unstable<'b, T, V, F>(&self, a: &'b mut [T], get: F) -> &'b mut [T]
+    where
+        V: Ord,
+        F: FnMut(&T, &C) -> Option<V>,
+    {
+        pdqsort::sort_by(a, self.comparator(get))
+    }
+}
+
+impl SortSpec<String> {
+    /// Parses a specification written like an `ORDER BY` clause, such as
+    /// `"a asc, b desc nulls last, c"`.
+    ///
+    /// Each key is a column name, optionally followed by `asc` or `desc` and
+    /// then by `nulls first` or `nulls last`. Keywords are case insensitive.
+    pub fn parse(s: &str) -> Result<Self, ParseError> {
+        if s.trim().is_empty() {
+            return Err(ParseError::Empty);
+        }
+
+        let mut spec = SortSpec::new();
+
+        for (index, key) in s.split(',').enumerate() {
+            let mut tokens = key.split_whitespace();
+            let column = tokens
+                .next()
+                .ok_or(ParseError::MissingColumn { key: index })?;
+            let mut token = tokens.next();
+            let mut direction = Direction::Ascending;
+
+            if let Some(t) = token {
+                if t.eq_ignore_ascii_case("asc") {
+                    token = tokens.next();
+                } else if t.eq_ignore_ascii_case("desc") {
+                    direction = Direction::Descending;
+                    token = tokens.next();
+                }
+            }
+
+            let mut nulls = Nulls::default_for(direction);
+
+            if let Some(t) = token.filter(|t| t.eq_ignore_ascii_case("nulls")) {
+                nulls = match tokens.next() {
+                    Some(p) if p.eq_ignore_ascii_case("first") => Nulls::First,
+                    Some(p) if p.eq_ignore_ascii_case("last") => Nulls::Last,
+                    other => {
+                        return Err(ParseError::UnexpectedToken {
+                            key: index,
+                            token: other.unwrap_or(t).to_string(),
+                        })
+                    }
+                };
+                token = tokens.next();
+            }
+
+            if let Some(t) = token {
+                return Err(ParseError::UnexpectedToken {
+                    key: index,
+                    token: t.to_string(),
+                });
+            }
+
+            spec = spec.then_with_nulls(column.to_string(), direction, nulls);
+        }
+
+        Ok(spec)
+    }
+}
+
+impl FromStr for SortSpec<String> {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+/// The error returned by [`SortSpec::parse`]. Keys are counted from 0.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseError {
+    /// The specification has no keys at all.
+    Empty,
+    /// A key, such as the middle one of `"a, , b"`, has no column name.
+    MissingColumn { key: usize },
+    /// A key has a word that is not where a keyword may go, or `nulls` is not
+    /// followed by `first` or `last`.
+    UnexpectedToken { key: usize, token: String },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Empty => write!(f, "empty sort specification"),
+            ParseError::MissingColumn { key } => {
+                write!(f, "sort key {} has no column name", key + 1)
+            }
+            ParseError::UnexpectedToken { key, token } => {
+                write!(f, "unexpected {token:?} in sort key {}", key + 1)
+            }
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use rand::distributions::Uniform;
+    use rand::Rng;
+
+    use super::*;
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Row {
+        region: Option<&'static str>,
+        sales: Option<i64>,
+        id: i64,
+    }
+
+    fn row(region: Option<&'static str>, sales: Option<i64>, id: i64) -> Row {
+        Row { region, sales, id }
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+    enum Value {
+        Int(i64),
+        Text(&'static str),
+    }
+
+    fn get(r: &Row, column: &String) -> Option<Value> {
+        match column.as_str() {
+            "region" => r.region.map(Value::Text),
+            "sales" => r.sales.map(Value::Int),
+            "id" => Some(Value::Int(r.id)),
+            _ => panic!("unknown column {column}"),
+        }
+    }
+
+    /// The state of McIlroy's adversary from "A Killer Adversary for Quicksort".
+    struct Adversary {
+        val: Vec<usize>,
+        solid: usize,
+        candidate: usize,
+    }
+
+    /// A column value that answers its comparisons with the adversary.
+    struct Gas<'a> {
+        index: usize,
+        adversary: &'a RefCell<Adversary>,
+    }
+
+    impl Ord for Gas<'_> {
+        fn cmp(&self, other: &Self) -> Ordering {
+            let mut adversary = self.adversary.borrow_mut();
+            let (x, y) = (self.index, other.index);
+            let gas = adversary.val.len();
+
+            if adversary.val[x] == gas && adversary.val[y] == gas {
+                let frozen = if x == adversary.candidate { x } else { y };
+                adversary.val[frozen] = adversary.solid;
+                adversary.solid += 1;
+            }
+
+            if adversary.val[x] == gas {
+                adversary.candidate = x;
+            } else if adversary.val[y] == gas {
+                adversary.candidate = y;
+            }
+
+            adversary.val[x].cmp(&adversary.val[y])
+        }
+    }
+
+    impl PartialOrd for Gas<'_> {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl PartialEq for Gas<'_> {
+        fn eq(&self, other: &Self) -> bool {
+            self.cmp(other) == Ordering::Equal
+        }
+    }
+
+    impl Eq for Gas<'_> {}
+
+    /// Builds an input that is as bad as possible for [`SortSpec::sort_unstable`].
+    fn killer_input(size: usize) -> Vec<usize> {
+        let adversary = RefCell::new(Adversary {
+            val: vec![size; size],
+            solid: 0,
+            candidate: 0,
+        });
+
+        let spec = SortSpec::parse("sales").unwrap();
+        let mut indices = (0..size).collect::<Vec<usize>>();
+        spec.sort_unstable(&mut indices, |&index, _| {
+            Some(Gas {
+                index,
+                adversary: &adversary,
+            })
+        });
+
+        adversary.into_inner().val
+    }
+
+    #[test]
+    fn test_parse() {
+        let spec = SortSpec::parse("a asc, b DESC Nulls Last, c").unwrap();
+        assert_eq!(
+            spec,
+            SortSpec::new()
+                .asc("a".to_string())
+                .then_with_nulls("b".to_string(), Direction::Descending, Nulls::Last)
+                .asc("c".to_string())
+        );
+        assert_eq!(spec.keys()[2].nulls, Nulls::Last);
+
+        let spec = "x desc".parse::<SortSpec<String>>().unwrap();
+        assert_eq!(spec.keys()[0].nulls, Nulls::First);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(SortSpec::parse(" "), Err(ParseError::Empty));
+        assert_eq!(
+            SortSpec::parse("a, , b"),
+            Err(ParseError::MissingColumn { key: 1 })
+        );
+        assert_eq!(
+            SortSpec::parse("a descending"),
+            Err(ParseError::UnexpectedToken {
+                key: 0,
+                token: "descending".to_string()
+            })
+        );
+        assert_eq!(
+            SortSpec::parse("a, b asc nulls"),
+            Err(ParseError::UnexpectedToken {
+                key: 1,
+                token: "nulls".to_string()
+            })
+        );
+        assert_eq!(
+            SortSpec::parse("a nulls last desc")
+                .unwrap_err()
+                .to_string(),
+            "unexpected \"desc\" in sort key 1"
+        );
+    }
+
+    #[test]
+    fn test_sort() {
+        let mut rows = vec![
+            row(Some("west"), Some(10), 0),
+            row(None, Some(30), 1),
+            row(Some("east"), None, 2),
+            row(Some("west"), Some(20), 3),
+            row(Some("east"), Some(10), 4),
+            row(Some("west"), None, 5),
+            row(Some("east"), Some(10), 6),
+        ];
+
+        let spec = SortSpec::parse("region, sales desc nulls last").unwrap();
+        spec.sort(&mut rows, get);
+        assert_eq!(
+            rows.iter().map(|r| r.id).collect::<Vec<i64>>(),
+            vec![4, 6, 2, 3, 0, 5, 1]
+        );
+
+        let spec = SortSpec::parse("region nulls first, sales, id desc").unwrap();
+        spec.sort_unstable(&mut rows, get);
+        assert_eq!(
+            rows.iter().map(|r| r.id).collect::<Vec<i64>>(),
+            vec![1, 6, 4, 2, 0, 3, 5]
+        );
+
+        // The comparator feeds any of the sorts.
+        crate::heap_sort::sort_by(
+            &mut rows,
+            SortSpec::new().desc("id".to_string()).comparator(get),
+        );
+        assert_eq!(
+            rows.iter().map(|r| r.id).collect::<Vec<i64>>(),
+            vec![6, 5, 4, 3, 2, 1, 0]
+        );
+    }
+
+    #[test]
+    fn test_sort_unstable_killer() {
+        let size = 5000;
+        let mut rows = killer_input(size)
+            .into_iter()
+            .enumerate()
+            .map(|(id, sales)| row(None, Some(sales as i64), id as i64))
+            .collect::<Vec<Row>>();
+
+        let spec = SortSpec::parse("sales").unwrap();
+        let mut count = 0;
+        spec.sort_unstable(&mut rows, |r, column| {
+            count += 1;
+            get(r, column)
+        });
+
+        // Every comparison gets the column of both rows.
+        assert!(count / 2 < 4 * size * 13);
+        assert!(rows.windows(2).all(|w| w[0].sales <= w[1].sales));
+    }
+
+    #[test]
+    fn test_sort_random() {
+        let mut rng = rand::thread_rng();
+        let range = Uniform::new_inclusive(0i64, 5);
+        let mut rows = (0..5000)
+            .map(|id| {
+                let sales = rng.sample(range);
+                let region = ["north", "south", "east"][rng.sample(range) as usize % 3];
+                row(
+                    (sales != 0).then_some(region),
+                    (sales != 5).then_some(sales),
+                    id,
+                )
+            })
+            .collect::<Vec<Row>>();
+
+        let spec = SortSpec::parse("sales desc nulls last, region asc nulls first").unwrap();
+        let mut expected = rows.clone();
+        expected.sort_by(|x, y| {
+            let sales = match (x.sales, y.sales) {
+                (Some(u), Some(v)) => v.cmp(&u),
+                (u, v) => v.is_some().cmp(&u.is_some()),
+            };
+            sales
+                .then(x.region.is_some().cmp(&y.region.is_some()))
+                .then(x.region.cmp(&y.region))
+        });
+
+        spec.sort(&mut rows, get);
+        assert_eq!(rows, expected);
+    }
+}
@@ -5,6 +5,11 @@
 //! <https://github.com/rust-unofficial/too-many-lists/blob/bec3afe0c33ff2bdce6895126055e4c5fa0dbd7d/lists/src/second.rs>
 //!
 //! <https://github.com/rust-lang/rust/blob/cb9467515b5a9b15aaa905683c6b4dd9e851056c/library/alloc/src/collections/linked_list.rs>
+//!
+//! <https://www.chiark.greenend.org.uk/~sgtatham/algorithms/listsort.html>
+
+use std::cmp::Ordering;
+use std::mem;
 
 /// A stack implemented with a linked list.
 #[derive(Debug)]
@@ -97,7 +102,7 @@ impl<T> Stack<T> {
 
     /// Provides a forward iterator.
     #[inline]
-    pub fn iter(&self) -> Iter<T> {
+    pub fn iter(&self) -> Iter<'_, T> {
         let head: &Link<T> = &self.head;
 
         let next = match head {
@@ -113,7 +118,7 @@ impl<T> Stack<T> {
 
     /// Provides a forward iterator with mutable references.
     #[inline]
-    pub fn iter_mut(&mut self) -> IterMut<T> {
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
         let head: &mut Link<T> = &mut self.head;
 
         let next = match head {
@@ -126,6 +131,137 @@ impl<T> Stack<T> {
 
         IterMut { next }
     }
+
+    /// Sorts the stack, so that it is popped, and iterated, in ascending order.
+    ///
+    /// The nodes are relinked, the elements are never moved or copied.
+    pub fn sort(&mut self)
+    where
+        T: Ord,
+    {
+        self.sort_by(T::cmp)
+    }
+
+    /// Sorts the stack with a comparator function.
+    ///
+    /// The sort is stable: equal elements keep their order from the top of the
+    /// stack down.
+    ///
+    /// If `compare` panics, the stack keeps all of its elements, in an
+    /// unspecified order.
+    pub fn sort_by<F>(&mut self, mut compare: F)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        // Bin `i` holds a sorted list of `2^i` nodes, or none, like the bits of
+        // a binary counter. Higher bins hold nodes from nearer the top.
+        let rest = Stack {
+            head: self.head.take(),
+        };
+        let mut lists = SortLists {
+            stack: self,
+            bins: std::array::from_fn(|_| Stack::new()),
+            rest,
+            carry: Stack::new(),
+            merged: Stack::new(),
+        };
+
+        while let Some(mut boxed_node) = lists.rest.head.take() {
+            lists.rest.head = boxed_node.next.take();
+            lists.carry.head = Some(boxed_node);
+
+            for bin in lists.bins.iter_mut() {
+                if bin.head.is_none() {
+                    *bin = mem::take(&mut lists.carry);
+                    break;
+                }
+
+                merge(bin, &mut lists.carry, &mut lists.merged, &mut compare);
+                lists.carry = mem::take(&mut lists.merged);
+            }
+        }
+
+        for bin in lists.bins.iter_mut() {
+            merge(bin, &mut lists.carry, &mut lists.merged, &mut compare);
+            lists.carry = mem::take(&mut lists.merged);
+        }
+    }
+
+    /// Sorts the stack with a key extraction function.
+    ///
+    /// The sort is stable: equal elements keep their order from the top of the
+    /// stack down.
+    pub fn sort_by_key<K, F>(&mut self, mut f: F)
+    where
+        K: Ord,
+        F: FnMut(&T) -> K,
+    {
+        self.sort_by(|x, y| f(x).cmp(&f(y)))
+    }
+}
+
+/// The lists of a sort in progress. Dropping them links all their nodes back
+/// into `stack`, so a panicking comparison loses no elements.
+struct SortLists<'a, T> {
+    stack: &'a mut Stack<T>,
+    bins: [Stack<T>; usize::BITS as usize],
+    rest: Stack<T>,
+    carry: Stack<T>,
+    merged: Stack<T>,
+}
+
+impl<T> Drop for SortLists<'_, T> {
+    fn drop(&mut self) {
+        // After a sort only `carry` is left, and it is not walked.
+        let mut head = self.carry.head.take();
+
+        for list in [&mut self.merged, &mut self.rest]
+            .into_iter()
+            .chain(self.bins.iter_mut())
+        {
+            let mut tail = &mut list.head;
+
+            while let Some(node) = tail {
+                tail = &mut node.next;
+            }
+
+            *tail = head;
+            head = list.head.take();
+        }
+
+        self.stack.head = head;
+    }
+}
+
+/// Merges the sorted lists `left` and `right` into the empty `merged` by
+/// relinking their nodes, taking from `left` first when elements are equal.
+///
+/// Every node is always in one of the three lists, even if `compare` panics.
+fn merge<T, F>(left: &mut Stack<T>, right: &mut Stack<T>, merged: &mut Stack<T>, compare: &mut F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let mut tail: &mut Link<T> = &mut merged.head;
+
+    while let (Some(l), Some(r)) = (&left.head, &right.head) {
+        let source = if compare(&r.elem, &l.elem) == Ordering::Less {
+            &mut right.head
+        } else {
+            &mut left.head
+        };
+
+        if let Some(mut boxed_node) = source.take() {
+            *source = boxed_node.next.take();
+            tail = &mut tail.insert(boxed_node).next;
+        }
+    }
+
+    // One list ran out, link the rest of the other one.
+    *tail = if left.head.is_some() {
+        left.head.take()
+    } else {
+        right.head.take()
+    };
 }
 
 impl<T> Drop for Stack<T> {
@@ -324,4 +460,143 @@ mod tests {
         assert_eq!(iter.next(), Some(&mut 2));
         assert_eq!(iter.next(), Some(&mut 1));
     }
+
+    #[test]
+    fn test_sort() {
+        let mut stack = Stack::new();
+        stack.sort();
+        assert_eq!(stack.pop(), None);
+
+        for x in [2, 4, 6, 2, 1, 8, 10, 3, 5, 7, 0, 9, 1] {
+            stack.push(x);
+        }
+
+        stack.sort();
+        assert_eq!(
+            stack.iter().copied().collect::<Vec<i32>>(),
+            vec![0, 1, 1, 2, 2, 3, 4, 5, 6, 7, 8, 9, 10]
+        );
+
+        stack.sort_by(|x, y| y.cmp(x));
+        assert_eq!(stack.pop(), Some(10));
+        assert_eq!(stack.pop(), Some(9));
+    }
+
+    #[test]
+    fn test_sort_by_key_stable() {
+        let mut stack = Stack::new();
+
+        for (i, word) in ["pear", "fig", "apple", "kiwi", "plum", "date", "banana"]
+            .iter()
+            .enumerate()
+        {
+            stack.push((word.to_string(), i));
+        }
+
+        // Pushed last is on top, so equal lengths come out in reverse push order.
+        stack.sort_by_key(|x| x.0.len());
+        assert_eq!(
+            stack.iter().map(|x| x.0.as_str()).collect::<Vec<&str>>(),
+            vec!["fig", "date", "plum", "kiwi", "pear", "apple", "banana"]
+        );
+    }
+
+    #[test]
+    fn test_sort_relinks_nodes() {
+        /// The address of every node with its element, from the top down.
+        fn nodes<T: Copy>(stack: &Stack<T>) -> Vec<(*const Node<T>, T)> {
+            let mut nodes = vec![];
+            let mut link = stack.head.as_deref();
+
+            while let Some(node) = link {
+                nodes.push((node as *const Node<T>, node.elem));
+                link = node.next.as_deref();
+            }
+
+            nodes
+        }
+
+        let mut stack = Stack::new();
+
+        for x in [3, 1, 2, 5, 4] {
+            stack.push(x);
+        }
+
+        let before = nodes(&stack);
+        stack.sort();
+        let after = nodes(&stack);
+
+        // Each element is still in the node it was pushed in.
+        assert_eq!(
+            after,
+            [before[3], before[2], before[4], before[0], before[1]]
+        );
+        assert_eq!(
+            after.iter().map(|x| x.1).collect::<Vec<i32>>(),
+            vec![1, 2, 3, 4, 5]
+        );
+    }
+
+    #[test]
+    fn test_sort_panic_keeps_elements() {
+        use std::cell::Cell;
+        use std::panic::{self, AssertUnwindSafe};
+        use std::rc::Rc;
+
+        struct Counted {
+            id: usize,
+            drops: Rc<Cell<usize>>,
+        }
+
+        impl Drop for Counted {
+            fn drop(&mut self) {
+                self.drops.set(self.drops.get() + 1);
+            }
+        }
+
+        let size = 200000;
+        let drops = Rc::new(Cell::new(0));
+        let mut stack = Stack::new();
+
+        for id in 0..size {
+            stack.push(Counted {
+                id: id * 7919 % size,
+                drops: drops.clone(),
+            });
+        }
+
+        let mut count = 0;
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            stack.sort_by(|x, y| {
+                count += 1;
+                if count > size * 3 {
+                    panic!("comparison limit reached");
+                }
+                x.id.cmp(&y.id)
+            });
+        }));
+        assert!(result.is_err());
+
+        // Long lists were being merged, and were all linked back.
+        assert_eq!(drops.get(), 0);
+        let mut ids = stack.iter().map(|x| x.id).collect::<Vec<usize>>();
+        ids.sort();
+        assert!(ids.into_iter().eq(0..size));
+
+        drop(stack);
+        assert_eq!(drops.get(), size);
+    }
+
+    #[test]
+    fn test_sort_long() {
+        let size = 200_000;
+        let mut stack = Stack::new();
+
+        for x in 0..size {
+            stack.push((x * 7919) % size);
+        }
+
+        stack.sort();
+        assert!(stack.iter().copied().eq(0..size));
+    }
 }
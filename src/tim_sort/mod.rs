@@ -0,0 +1,757 @@
+//! # TimSort
+//!
+//! This is adapted from
+//!
+//! <https://en.wikipedia.org/wiki/Timsort>
+//!
+//! <https://github.com/python/cpython/blob/v3.12.0/Objects/listsort.txt>
+//!
+//! <https://github.com/rust-lang/rust/blob/cb9467515b5a9b15aaa905683c6b4dd9e851056c/library/alloc/src/slice.rs>
+//!
+//! Test case is derived from
+//!
+//! <https://github.com/rust-lang/rust/blob/60bd3f96779dbe6bd206dae09395e9af7d580552/library/alloc/src/collections/binary_heap/tests.rs>
+
+use std::cmp::Ordering;
+use std::mem::MaybeUninit;
+use std::ptr;
+use std::slice;
+
+use crate::insertion_sort;
+use crate::merge_sort::{self, MergeHole};
+
+/// Arrays shorter than this are sorted as a single run.
+const MIN_MERGE: usize = 32;
+
+/// Gallops shorter than this count as failures, and the initial number of wins
+/// in a row that starts galloping.
+const MIN_GALLOP: usize = 7;
+
+/// A sorted run `a[start, start + len - 1]`.
+#[derive(Clone, Copy, Debug)]
+struct Run {
+    start: usize,
+    len: usize,
+}
+
+/// Sorting the entire array.
+pub fn sort<T: Ord>(a: &mut [T]) -> &mut [T] {
+    sort_by(a, T::cmp)
+}
+
+/// Sorting the entire array with a comparator function.
+///
+/// The sort is stable: equal elements keep their original order.
+pub fn sort_by<T, F>(a: &mut [T], mut compare: F) -> &mut [T]
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let size = a.len();
+
+    if size <= 1 {
+        return a;
+    }
+
+    tim_sort(a, size, &mut compare);
+    a
+}
+
+/// Sorting the entire array with a key extraction function.
+///
+/// The sort is stable: elements with equal keys keep their original order.
+pub fn sort_by_key<T, K, F>(a: &mut [T], mut f: F) -> &mut [T]
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    sort_by(a, |x, y| f(x).cmp(&f(y)))
+}
+
+/// Finds the natural runs of `a[]`, extends the short ones, and merges them
+/// while keeping the run lengths balanced.
+fn tim_sort<T, F>(a: &mut [T], n: usize, compare: &mut F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let min_run = min_run_length(n);
+
+    // Work array, large enough to hold the shorter run of any merge.
+    let mut b = merge_sort::scratch(n / 2);
+
+    // Stack of pending runs, in order from left to right.
+    let mut runs: Vec<Run> = vec![];
+    let mut min_gallop = MIN_GALLOP;
+    let mut start = 0;
+
+    while start < n {
+        let mut len = count_run_and_make_ascending(&mut a[start..], compare);
+
+        // If the run is short, extend it to `min_run` items.
+        if len < min_run {
+            let force = min_run.min(n - start);
+            insertion_sort::binary_insertion_sort(&mut a[start..start + force], len, &mut *compare);
+            len = force;
+        }
+
+        runs.push(Run { start, len });
+        merge_collapse(a, &mut runs, &mut b, &mut min_gallop, compare);
+        start += len;
+    }
+
+    // Merge all remaining runs.
+    while runs.len() > 1 {
+        let n = runs.len();
+        let at = if n >= 3 && runs[n - 3].len < runs[n - 1].len {
+            n - 3
+        } else {
+            n - 2
+        };
+        merge_at(a, &mut runs, at, &mut b, &mut min_gallop, compare);
+    }
+}
+
+/// Returns the minimum run length, such that `n / min_run` is a power of two,
+/// or slightly less than one.
+fn min_run_length(mut n: usize) -> usize {
+    let mut r = 0;
+
+    while n >= MIN_MERGE {
+        r |= n & 1;
+        n >>= 1;
+    }
+
+    n + r
+}
+
+/// Returns the length of the run at the start of `a[]`, reversing it first
+/// if it is strictly descending.
+///
+/// Descending runs have to be strict, or reversing them would break stability.
+fn count_run_and_make_ascending<T, F>(a: &mut [T], compare: &mut F) -> usize
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let size = a.len();
+
+    if size <= 1 {
+        return size;
+    }
+
+    let mut end = 2;
+
+    if compare(&a[1], &a[0]) == Ordering::Less {
+        // Strictly descending.
+        while end < size && compare(&a[end], &a[end - 1]) == Ordering::Less {
+            end += 1;
+        }
+        a[..end].reverse();
+    } else {
+        // Ascending.
+        while end < size && compare(&a[end], &a[end - 1]) != Ordering::Less {
+            end += 1;
+        }
+    }
+
+    end
+}
+
+/// Merges runs until the stack invariants hold again:
+///
+/// 1. `runs[n - 3].len > runs[n - 2].len + runs[n - 1].len`
+/// 2. `runs[n - 2].len > runs[n - 1].len`
+///
+/// The first one is also checked one level deeper, see
+/// <http://envisage-project.eu/proving-android-java-and-python-sorting-algorithm-is-broken-and-how-to-fix-it/>.
+fn merge_collapse<T, F>(
+    a: &mut [T],
+    runs: &mut Vec<Run>,
+    b: &mut [MaybeUninit<T>],
+    min_gallop: &mut usize,
+    compare: &mut F,
+) where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    while runs.len() > 1 {
+        let n = runs.len();
+
+        if (n >= 3 && runs[n - 3].len <= runs[n - 2].len + runs[n - 1].len)
+            || (n >= 4 && runs[n - 4].len <= runs[n - 3].len + runs[n - 2].len)
+        {
+            let at = if runs[n - 3].len < runs[n - 1].len {
+                n - 3
+            } else {
+                n - 2
+            };
+            merge_at(a, runs, at, b, min_gallop, compare);
+        } else if runs[n - 2].len <= runs[n - 1].len {
+            merge_at(a, runs, n - 2, b, min_gallop, compare);
+        } else {
+            break;
+        }
+    }
+}
+
+/// Merges `runs[at]` with `runs[at + 1]`.
+///
+/// Galloping first skips the head of the left run and the tail of the right run
+/// that are already in place, so only the overlapping part is merged by
+/// [`merge_lo`] or [`merge_hi`].
+fn merge_at<T, F>(
+    a: &mut [T],
+    runs: &mut Vec<Run>,
+    at: usize,
+    b: &mut [MaybeUninit<T>],
+    min_gallop: &mut usize,
+    compare: &mut F,
+) where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let Run { start, len: len1 } = runs[at];
+    let len2 = runs[at + 1].len;
+
+    runs[at].len = len1 + len2;
+    runs.remove(at + 1);
+
+    let middle = start + len1;
+    let end = middle + len2;
+
+    // Items of the left run that are not greater than the right run's first item
+    // are already in place.
+    let begin = start + gallop_right(&a[middle], &a[start..middle], compare);
+
+    if begin == middle {
+        return;
+    }
+
+    // Likewise items of the right run that are not less than the left run's last item.
+    let end = middle + gallop_left(&a[middle - 1], &a[middle..end], compare);
+
+    if end == middle {
+        return;
+    }
+
+    let v = &mut a[begin..end];
+    let mid = middle - begin;
+    assert!(b.len() >= mid.min(v.len() - mid));
+
+    // SAFETY: both runs are non-empty, and `b[]` has room for the shorter one.
+    unsafe {
+        if mid <= v.len() - mid {
+            merge_lo(v, mid, b, min_gallop, compare);
+        } else {
+            merge_hi(v, mid, b, min_gallop, compare);
+        }
+    }
+}
+
+/// Merges the runs `v[..mid]` and `v[mid..]` forwards, moving the shorter left
+/// run out into `b[]` first.
+///
+/// Items are taken one at a time until one run wins `min_gallop` times in a
+/// row, then both runs are galloped through, until neither gallop skips at least
+/// [`MIN_GALLOP`] items. Every round of galloping lowers `min_gallop`, and
+/// leaving it raises `min_gallop`, so data that rewards galloping enters it
+/// sooner, and random data soon stops trying.
+///
+/// # Safety
+///
+/// Both runs must be non-empty, and `b[]` must hold at least `mid` items.
+unsafe fn merge_lo<T, F>(
+    v: &mut [T],
+    mid: usize,
+    b: &mut [MaybeUninit<T>],
+    min_gallop: &mut usize,
+    compare: &mut F,
+) where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let len = v.len();
+    let v = v.as_mut_ptr();
+    let buf = b.as_mut_ptr() as *mut T;
+
+    // Every item is always owned by exactly one of `v[]` or `b[]`: `hole`
+    // tracks the items still in `b[]` and where they belong in `v[]`, and
+    // [dest, right) is the gap, exactly as long as [start, end).
+    ptr::copy_nonoverlapping(v, buf, mid);
+    let mut hole = MergeHole {
+        start: buf,
+        end: buf.add(mid),
+        dest: v,
+    };
+
+    let mut right = v.add(mid);
+    let right_end = v.add(len);
+
+    'merge: loop {
+        let mut left_wins = 0;
+        let mut right_wins = 0;
+
+        // Take from the right run only if it is strictly smaller.
+        while left_wins < *min_gallop && right_wins < *min_gallop {
+            if compare(&*right, &*hole.start) == Ordering::Less {
+                ptr::copy_nonoverlapping(right, hole.dest, 1);
+                right = right.add(1);
+                right_wins += 1;
+                left_wins = 0;
+            } else {
+                ptr::copy_nonoverlapping(hole.start, hole.dest, 1);
+                hole.start = hole.start.add(1);
+                left_wins += 1;
+                right_wins = 0;
+            }
+
+            hole.dest = hole.dest.add(1);
+
+            if hole.start == hole.end || right == right_end {
+                break 'merge;
+            }
+        }
+
+        loop {
+            // Left items not greater than the next right item go first.
+            let left = slice::from_raw_parts(hole.start, hole.end.offset_from(hole.start) as usize);
+            let count = gallop_right(&*right, left, compare);
+            ptr::copy_nonoverlapping(hole.start, hole.dest, count);
+            hole.start = hole.start.add(count);
+            hole.dest = hole.dest.add(count);
+
+            if hole.start == hole.end {
+                break 'merge;
+            }
+
+            // Then right items strictly less than the next left item.
+            let rest = slice::from_raw_parts(right, right_end.offset_from(right) as usize);
+            let taken = gallop_forward(rest, |x| compare(x, &*hole.start) == Ordering::Less);
+            ptr::copy(right, hole.dest, taken);
+            right = right.add(taken);
+            hole.dest = hole.dest.add(taken);
+
+            if right == right_end {
+                break 'merge;
+            }
+
+            if count < MIN_GALLOP && taken < MIN_GALLOP {
+                *min_gallop += 1;
+                break;
+            }
+
+            *min_gallop = min_gallop.saturating_sub(1).max(1);
+        }
+    }
+    // Whatever is left in `b[]` is moved into the gap when `hole` is dropped.
+}
+
+/// Merges the runs `v[..mid]` and `v[mid..]` backwards, moving the shorter
+/// right run out into `b[]` first, and galloping like [`merge_lo`].
+///
+/// # Safety
+///
+/// Both runs must be non-empty, and `b[]` must hold at least `v.len() - mid`
+/// items.
+unsafe fn merge_hi<T, F>(
+    v: &mut [T],
+    mid: usize,
+    b: &mut [MaybeUninit<T>],
+    min_gallop: &mut usize,
+    compare: &mut F,
+) where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let len = v.len();
+    let v = v.as_mut_ptr();
+    let buf = b.as_mut_ptr() as *mut T;
+
+    // The left run is `v[..dest]`, and [dest, out) is the gap, exactly as long
+    // as the items [start, end) still in `b[]`.
+    ptr::copy_nonoverlapping(v.add(mid), buf, len - mid);
+    let mut hole = MergeHole {
+        start: buf,
+        end: buf.add(len - mid),
+        dest: v.add(mid),
+    };
+
+    let mut out = v.add(len);
+
+    'merge: loop {
+        let mut left_wins = 0;
+        let mut right_wins = 0;
+
+        // Take from the left run only if it is strictly greater.
+        while left_wins < *min_gallop && right_wins < *min_gallop {
+            out = out.sub(1);
+
+            if compare(&*hole.end.sub(1), &*hole.dest.sub(1)) == Ordering::Less {
+                hole.dest = hole.dest.sub(1);
+                ptr::copy_nonoverlapping(hole.dest, out, 1);
+                left_wins += 1;
+                right_wins = 0;
+            } else {
+                hole.end = hole.end.sub(1);
+                ptr::copy_nonoverlapping(hole.end, out, 1);
+                right_wins += 1;
+                left_wins = 0;
+            }
+
+            if hole.dest == v || hole.start == hole.end {
+                break 'merge;
+            }
+        }
+
+        loop {
+            // Left items strictly greater than the last right item go last.
+            let left = slice::from_raw_parts(v, hole.dest.offset_from(v) as usize);
+            let key = &*hole.end.sub(1);
+            let count =
+                left.len() - gallop_backward(left, |x| compare(x, key) != Ordering::Greater);
+            out = out.sub(count);
+            hole.dest = hole.dest.sub(count);
+            ptr::copy(hole.dest, out, count);
+
+            if hole.dest == v {
+                break 'merge;
+            }
+
+            // Then right items not less than the last left item.
+            let rest = slice::from_raw_parts(hole.start, hole.end.offset_from(hole.start) as usize);
+            let key = &*hole.dest.sub(1);
+            let taken = rest.len() - gallop_backward(rest, |x| compare(x, key) == Ordering::Less);
+            out = out.sub(taken);
+            hole.end = hole.end.sub(taken);
+            ptr::copy_nonoverlapping(hole.end, out, taken);
+
+            if hole.start == hole.end {
+                break 'merge;
+            }
+
+            if count < MIN_GALLOP && taken < MIN_GALLOP {
+                *min_gallop += 1;
+                break;
+            }
+
+            *min_gallop = min_gallop.saturating_sub(1).max(1);
+        }
+    }
+    // Whatever is left in `b[]` is moved into the gap when `hole` is dropped.
+}
+
+/// Returns how many items at the start of the sorted `run[]` are `<= key`,
+/// galloping forwards from the start.
+fn gallop_right<T, F>(key: &T, run: &[T], compare: &mut F) -> usize
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    gallop_forward(run, |x| compare(x, key) != Ordering::Greater)
+}
+
+/// Returns how many items at the start of the sorted `run[]` are `< key`,
+/// galloping backwards from the end.
+fn gallop_left<T, F>(key: &T, run: &[T], compare: &mut F) -> usize
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    gallop_backward(run, |x| compare(x, key) == Ordering::Less)
+}
+
+/// Returns the length of the prefix of `run[]` where `pred` holds, probing
+/// items 1, 3, 7, ... from the start before a binary search.
+fn gallop_forward<T, P>(run: &[T], mut pred: P) -> usize
+where
+    P: FnMut(&T) -> bool,
+{
+    let size = run.len();
+
+    // pred holds on run[..lo], and fails on run[hi - 1] unless hi is past the end.
+    let mut lo = 0;
+    let mut hi = 1;
+
+    while hi <= size && pred(&run[hi - 1]) {
+        lo = hi;
+        hi = hi * 2 + 1;
+    }
+
+    let hi = hi.min(size);
+    lo + run[lo..hi].partition_point(pred)
+}
+
+/// Returns the length of the prefix of `run[]` where `pred` holds, probing
+/// items 1, 3, 7, ... from the end before a binary search.
+fn gallop_backward<T, P>(run: &[T], mut pred: P) -> usize
+where
+    P: FnMut(&T) -> bool,
+{
+    let size = run.len();
+
+    // pred fails on run[hi..], and holds on run[size - ofs] unless ofs is past the start.
+    let mut hi = size;
+    let mut ofs = 1;
+
+    while ofs <= size && !pred(&run[size - ofs]) {
+        hi = size - ofs;
+        ofs = ofs * 2 + 1;
+    }
+
+    let lo = size.saturating_sub(ofs);
+    lo + run[lo..hi].partition_point(pred)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::distributions::Uniform;
+    use rand::Rng;
+
+    use super::*;
+
+    fn check_orderly<T: Ord>(a: &[T]) {
+        if a.is_empty() {
+            return;
+        }
+
+        for i in 0..a.len() - 1 {
+            assert!(a[i] <= a[i + 1]);
+        }
+    }
+
+    fn count_comparisons(a: &mut [i32]) -> usize {
+        let mut count = 0;
+        sort_by(a, |x, y| {
+            count += 1;
+            x.cmp(y)
+        });
+        count
+    }
+
+    #[test]
+    fn test_basic() {
+        let mut data = vec![5, 9, 3];
+        let sorted = vec![3, 5, 9];
+        sort(&mut data);
+        check_orderly(&data);
+        assert_eq!(data, sorted);
+    }
+
+    #[test]
+    fn test_normal() {
+        let mut data = vec![2, 4, 6, 2, 1, 8, 10, 3, 5, 7, 0, 9, 1];
+        let sorted = vec![0, 1, 1, 2, 2, 3, 4, 5, 6, 7, 8, 9, 10];
+        sort(&mut data);
+        check_orderly(&data);
+        assert_eq!(data, sorted);
+    }
+
+    #[test]
+    fn test_batch() {
+        check_orderly(sort::<i32>(&mut []));
+        check_orderly(sort(&mut [5]));
+        check_orderly(sort(&mut [3, 2]));
+        check_orderly(sort(&mut [2, 3]));
+        check_orderly(sort(&mut [5, 1, 2]));
+        check_orderly(sort(&mut [1, 100, 2, 3]));
+        check_orderly(sort(&mut [1, 3, 5, 7, 9, 2, 4, 6, 8, 0]));
+        check_orderly(sort(&mut [2, 4, 6, 2, 1, 8, 10, 3, 5, 7, 0, 9, 1]));
+        check_orderly(sort(&mut [
+            9, 11, 9, 9, 9, 9, 11, 2, 3, 4, 11, 9, 0, 0, 0, 0,
+        ]));
+        check_orderly(sort(&mut [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10]));
+        check_orderly(sort(&mut [10, 9, 8, 7, 6, 5, 4, 3, 2, 1, 0]));
+        check_orderly(sort(&mut [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 0, 0, 0, 1, 2]));
+        check_orderly(sort(&mut [5, 4, 3, 2, 1, 5, 4, 3, 2, 1, 5, 4, 3, 2, 1]));
+    }
+
+    #[test]
+    fn test_sorted_is_linear() {
+        let mut data = (0..10000).collect::<Vec<i32>>();
+        assert_eq!(count_comparisons(&mut data), 9999);
+        check_orderly(&data);
+
+        let mut data = (0..10000).rev().collect::<Vec<i32>>();
+        assert_eq!(count_comparisons(&mut data), 9999);
+        check_orderly(&data);
+    }
+
+    #[test]
+    fn test_sorted_batches() {
+        let mut rng = rand::thread_rng();
+        let range = Uniform::new_inclusive(-50000, 50000);
+        let mut data = vec![];
+
+        for _ in 0..20 {
+            let mut batch = (&mut rng)
+                .sample_iter(range)
+                .take(2500)
+                .collect::<Vec<i32>>();
+            batch.sort();
+            data.extend(batch);
+        }
+
+        let size = data.len();
+        let count = count_comparisons(&mut data);
+        check_orderly(&data);
+        assert!(count < size * 6);
+    }
+
+    /// Two sorted runs, of `left` and `right` blocks of `block` items, whose
+    /// blocks take turns in the merged order. Blocks with the same `tie` value
+    /// on both sides hold equal keys.
+    fn interleaved_blocks(block: usize, left: usize, right: usize, tie: bool) -> Vec<(i32, usize)> {
+        let key = |i: usize, j: usize| {
+            if tie {
+                (i * 2) as i32
+            } else {
+                (i * block + j) as i32
+            }
+        };
+
+        let left = (0..left * 2)
+            .step_by(2)
+            .flat_map(|i| (0..block).map(move |j| key(i, j)));
+        let right = (1..right * 2)
+            .step_by(2)
+            .flat_map(|i| (0..block).map(move |j| key(i - tie as usize, j)));
+
+        left.chain(right).enumerate().map(|(i, x)| (x, i)).collect()
+    }
+
+    #[test]
+    fn test_gallops_interleaved_blocks() {
+        for (left, right) in [(50, 50), (70, 30), (30, 70)] {
+            let mut data = interleaved_blocks(100, left, right, false)
+                .into_iter()
+                .map(|x| x.0)
+                .collect::<Vec<i32>>();
+            let size = data.len();
+
+            // Finding the two runs takes `size - 1` comparisons, and merging
+            // them one item at a time would take about `size` more.
+            let count = count_comparisons(&mut data);
+            check_orderly(&data);
+            assert!(count < size + size / 4, "{count} comparisons");
+        }
+    }
+
+    #[test]
+    fn test_gallop_stable() {
+        for (left, right) in [(50, 50), (70, 30), (30, 70), (200, 3), (3, 200)] {
+            for block in [1, 5, 40] {
+                let mut data = interleaved_blocks(block, left, right, true);
+                let mut sorted = data.clone();
+                sorted.sort_by_key(|x| x.0);
+
+                sort_by_key(&mut data, |x| x.0);
+                assert_eq!(data, sorted);
+            }
+        }
+    }
+
+    #[test]
+    fn test_gallop_panic_safety() {
+        use std::cell::Cell;
+        use std::panic::{self, AssertUnwindSafe};
+        use std::rc::Rc;
+
+        struct Counted {
+            id: i32,
+            drops: Rc<Cell<usize>>,
+        }
+
+        impl Drop for Counted {
+            fn drop(&mut self) {
+                self.drops.set(self.drops.get() + 1);
+            }
+        }
+
+        for (left, right) in [(20, 20), (30, 10)] {
+            // Panic at points spread over the run search and the merge.
+            let total = count_comparisons(
+                &mut interleaved_blocks(20, left, right, false)
+                    .into_iter()
+                    .map(|x| x.0)
+                    .collect::<Vec<i32>>(),
+            );
+
+            for limit in (0..total).step_by(total / 10) {
+                let drops = Rc::new(Cell::new(0));
+                let mut data = interleaved_blocks(20, left, right, false)
+                    .into_iter()
+                    .map(|x| Counted {
+                        id: x.0,
+                        drops: drops.clone(),
+                    })
+                    .collect::<Vec<Counted>>();
+                let mut ids = data.iter().map(|x| x.id).collect::<Vec<i32>>();
+                let size = data.len();
+
+                let mut count = 0;
+                let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                    sort_by(&mut data, |x, y| {
+                        count += 1;
+                        if count > limit {
+                            panic!("comparison limit reached");
+                        }
+                        x.id.cmp(&y.id)
+                    });
+                }));
+                assert!(result.is_err());
+
+                // No element was lost or duplicated.
+                let mut after = data.iter().map(|x| x.id).collect::<Vec<i32>>();
+                ids.sort();
+                after.sort();
+                assert_eq!(ids, after);
+                assert_eq!(drops.get(), 0);
+
+                drop(data);
+                assert_eq!(drops.get(), size);
+            }
+        }
+    }
+
+    #[test]
+    fn test_stable() {
+        let mut rng = rand::thread_rng();
+        let range = Uniform::new_inclusive(0, 100);
+
+        for size in [10, 100, 1000, 50000] {
+            let mut data = (&mut rng)
+                .sample_iter(range)
+                .take(size)
+                .enumerate()
+                .map(|(i, x)| (x, i))
+                .collect::<Vec<(i32, usize)>>();
+
+            // Mix in some descending and sorted stretches.
+            data[size / 4..size / 2].sort_by(|x, y| y.cmp(x));
+            data[size / 2..size * 3 / 4].sort();
+
+            let mut sorted = data.clone();
+            sorted.sort_by_key(|x| x.0);
+
+            sort_by_key(&mut data, |x| x.0);
+            assert_eq!(data, sorted);
+        }
+    }
+
+    #[test]
+    fn test_strings() {
+        let mut data = ["pear", "apple", "fig", "banana", "cherry", "date", "apple"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<String>>();
+        sort(&mut data);
+        assert_eq!(
+            data,
+            ["apple", "apple", "banana", "cherry", "date", "fig", "pear"]
+        );
+    }
+
+    #[test]
+    fn test_random_large() {
+        let mut rng = rand::thread_rng();
+        let range = Uniform::new_inclusive(-50000, 50000);
+        let mut data = (&mut rng)
+            .sample_iter(range)
+            .take(50000)
+            .collect::<Vec<i32>>();
+        sort(&mut data);
+        check_orderly(&data);
+    }
+}